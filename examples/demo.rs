@@ -0,0 +1,26 @@
+use yamux::{Frame, Tag, Untyped};
+use zerocopy::AsBytes;
+
+fn main() {
+    // Parse some bytes into a frame
+    let mut bytes = [
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03,
+    ];
+    let mut frame = Frame::<&mut [u8], Untyped>::parse(&mut bytes[..]).unwrap();
+    println!("{frame:?}");
+    println!(
+        "frame version = {:?}, frame length = {:?}",
+        frame.version(),
+        frame.length()
+    );
+    println!("frame body = {:?}", frame.body());
+
+    // Get frame's whole bytes
+    println!("{:?}", frame.header().as_bytes());
+
+    // Update frame
+    frame.set_tag(Tag::GoAway);
+
+    // Get frame's whole bytes
+    println!("{:?}", frame.header().as_bytes());
+}
@@ -0,0 +1,82 @@
+//! [`asynchronous_codec`] support, and helpers reading and writing frames on
+//! [`AsyncRead`] and [`AsyncWrite`] streams of any executor.
+
+use alloc::vec::Vec;
+use asynchronous_codec::{Decoder, Encoder};
+use bytes::BytesMut;
+use futures_util::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io;
+use zerocopy::{AsBytes, ByteSlice};
+
+use super::{BytesFrame, CodecError, YamuxCodec};
+use crate::frame::{check_header, Frame, ParseConfig};
+use crate::header::{Marker, Untyped, HEADER_LEN};
+
+impl Decoder for YamuxCodec {
+    type Item = BytesFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesFrame>, CodecError> {
+        Ok(self.decode_frame(src)?)
+    }
+}
+
+impl Encoder for YamuxCodec {
+    type Item<'a> = BytesFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, frame: BytesFrame, dst: &mut BytesMut) -> Result<(), CodecError> {
+        self.encode_frame(&frame, dst);
+        Ok(())
+    }
+}
+
+/// Read one frame from `reader` into `buf`, returning it borrowed from `buf`,
+/// or `None` if the stream ends before a new frame starts.
+///
+/// `buf` is meant to be reused across calls, so that reading frames stops
+/// allocating once it has grown to the size of the largest frame.
+pub async fn read_frame<'b, R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &'b mut Vec<u8>,
+) -> io::Result<Option<Frame<&'b [u8], Untyped>>> {
+    read_frame_with(reader, buf, ParseConfig::default()).await
+}
+
+/// Read one frame from `reader` into `buf` following `config`, see
+/// [`read_frame`].
+pub async fn read_frame_with<'b, R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &'b mut Vec<u8>,
+    config: ParseConfig,
+) -> io::Result<Option<Frame<&'b [u8], Untyped>>> {
+    buf.resize(HEADER_LEN, 0);
+    let mut filled = 0;
+    while filled < HEADER_LEN {
+        match reader.read(&mut buf[filled..]).await {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    let (tag, body_len) = check_header::<Untyped>(buf, config)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    buf.resize(HEADER_LEN + body_len, 0);
+    reader.read_exact(&mut buf[HEADER_LEN..]).await?;
+    Frame::split_checked(&buf[..], tag, body_len, config)
+        .map(|(frame, _)| Some(frame))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Write `frame` to `writer`.
+pub async fn write_frame<W, B, T>(writer: &mut W, frame: &Frame<B, T>) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    B: ByteSlice,
+    T: Marker,
+{
+    writer.write_all(frame.header().as_bytes()).await?;
+    writer.write_all(frame.body()).await
+}
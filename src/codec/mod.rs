@@ -0,0 +1,90 @@
+//! Codecs producing and consuming frames that own their body, for use with
+//! `Framed` transports.
+//!
+//! Decoded bodies are split off the read buffer as [`Bytes`](bytes::Bytes),
+//! sharing its allocation instead of being copied. [`YamuxCodec`] implements the codec
+//! traits of `tokio_util` with the `tokio` feature, and of
+//! `asynchronous_codec` with the `futures` feature.
+
+use bytes::{BufMut, BytesMut};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::io;
+use zerocopy::AsBytes;
+
+use crate::error::FrameError;
+use crate::frame::ParseConfig;
+use crate::header::HEADER_LEN;
+pub use crate::split::BytesFrame;
+
+#[cfg(feature = "futures")]
+pub mod futures;
+#[cfg(feature = "tokio")]
+mod tokio;
+
+/// Error raised by the codecs.
+#[derive(Debug)]
+pub enum CodecError {
+    /// The transport failed.
+    Io(io::Error),
+    /// The peer sent an invalid frame.
+    Frame(FrameError),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Io(err) => write!(f, "transport error: {err}"),
+            CodecError::Frame(err) => write!(f, "invalid frame: {err}"),
+        }
+    }
+}
+
+impl Error for CodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CodecError::Io(err) => Some(err),
+            CodecError::Frame(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for CodecError {
+    fn from(err: io::Error) -> Self {
+        CodecError::Io(err)
+    }
+}
+
+impl From<FrameError> for CodecError {
+    fn from(err: FrameError) -> Self {
+        CodecError::Frame(err)
+    }
+}
+
+/// Codec for yamux frames, see the module documentation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct YamuxCodec {
+    config: ParseConfig,
+}
+
+impl YamuxCodec {
+    /// Codec parsing frames with the default [`ParseConfig`].
+    pub fn new() -> Self {
+        Self::with_config(ParseConfig::default())
+    }
+
+    /// Codec parsing frames following `config`.
+    pub fn with_config(config: ParseConfig) -> Self {
+        YamuxCodec { config }
+    }
+
+    fn decode_frame(&self, src: &mut BytesMut) -> Result<Option<BytesFrame>, FrameError> {
+        BytesFrame::split_from_with(src, self.config)
+    }
+
+    fn encode_frame(&self, frame: &BytesFrame, dst: &mut BytesMut) {
+        dst.reserve(HEADER_LEN + frame.body.len());
+        dst.put_slice(frame.header.as_bytes());
+        dst.put_slice(&frame.body);
+    }
+}
@@ -0,0 +1,24 @@
+//! [`tokio_util::codec`] support.
+
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{BytesFrame, CodecError, YamuxCodec};
+
+impl Decoder for YamuxCodec {
+    type Item = BytesFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesFrame>, CodecError> {
+        Ok(self.decode_frame(src)?)
+    }
+}
+
+impl Encoder<BytesFrame> for YamuxCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, frame: BytesFrame, dst: &mut BytesMut) -> Result<(), CodecError> {
+        self.encode_frame(&frame, dst);
+        Ok(())
+    }
+}
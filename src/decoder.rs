@@ -0,0 +1,248 @@
+//! Incremental decoding of frames split across reads.
+
+use alloc::vec::Vec;
+
+use crate::error::FrameError;
+use crate::frame::{check_prefix, Frame, ParseConfig};
+use crate::header::{Untyped, HEADER_LEN};
+
+/// Decoder turning chunks of bytes, as returned by `read`, into frames.
+///
+/// Frames fully contained in a chunk are borrowed from it. Only the bytes of a
+/// frame straddling chunks are copied, into a buffer owned by the decoder.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    // Bytes left over from previous chunks.
+    pending: Vec<u8>,
+    // Number of bytes at the front of `pending` already handed out as frames.
+    consumed: usize,
+    config: ParseConfig,
+}
+
+impl Decoder {
+    /// Decoder parsing frames with the default [`ParseConfig`].
+    pub fn new() -> Self {
+        Self::with_config(ParseConfig::default())
+    }
+
+    /// Decoder parsing frames following `config`.
+    pub fn with_config(config: ParseConfig) -> Self {
+        Decoder {
+            pending: Vec::new(),
+            consumed: 0,
+            config,
+        }
+    }
+
+    /// Feed `chunk` to the decoder, returning a [`Decode`] yielding the
+    /// frames it completes.
+    ///
+    /// Bytes of `chunk` not consumed as frames when the [`Decode`] is dropped
+    /// are kept for the next call.
+    pub fn decode<'d, 'c>(&'d mut self, chunk: &'c [u8]) -> Decode<'d, 'c> {
+        Decode {
+            decoder: self,
+            chunk,
+        }
+    }
+
+    /// Number of bytes buffered while waiting for the rest of a frame.
+    pub fn pending(&self) -> usize {
+        self.pending.len() - self.consumed
+    }
+
+    fn compact(&mut self) {
+        self.pending.drain(..self.consumed);
+        self.consumed = 0;
+    }
+}
+
+/// Frames completed by a chunk fed to a [`Decoder`].
+///
+/// Each frame borrows from the decoder or the chunk, so they are handed out
+/// one at a time by [`Decode::next_frame`] rather than through `Iterator`.
+#[derive(Debug)]
+pub struct Decode<'d, 'c> {
+    decoder: &'d mut Decoder,
+    chunk: &'c [u8],
+}
+
+impl Decode<'_, '_> {
+    /// Next complete frame, or `None` once more bytes are needed.
+    ///
+    /// After an error the decoder has lost track of frame boundaries: the
+    /// buffered bytes are dropped and the connection should be closed.
+    pub fn next_frame(&mut self) -> Option<Result<Frame<&[u8], Untyped>, FrameError>> {
+        self.decoder.compact();
+        if !self.decoder.pending.is_empty() {
+            return self.next_straddling();
+        }
+        match Frame::parse_prefix_with(self.chunk, self.decoder.config) {
+            Ok((frame, rest)) => {
+                self.chunk = rest;
+                Some(Ok(frame))
+            }
+            Err(FrameError::Incomplete { .. }) => None,
+            Err(err) => Some(Err(self.fail(err))),
+        }
+    }
+
+    /// Complete the frame at the front of the pending bytes with the bytes it
+    /// is missing from the chunk.
+    fn next_straddling(&mut self) -> Option<Result<Frame<&[u8], Untyped>, FrameError>> {
+        let config = self.decoder.config;
+        let (tag, body_len) = loop {
+            let pending = &self.decoder.pending[..];
+            match check_prefix::<Untyped>(pending, config) {
+                Ok(checked) => break checked,
+                Err(FrameError::Incomplete { needed }) => {
+                    if self.chunk.is_empty() {
+                        return None;
+                    }
+                    let (missing, rest) = self.chunk.split_at(needed.min(self.chunk.len()));
+                    self.decoder.pending.extend_from_slice(missing);
+                    self.chunk = rest;
+                }
+                Err(err) => return Some(Err(self.fail(err))),
+            }
+        };
+        self.decoder.consumed = HEADER_LEN + body_len;
+        let pending = &self.decoder.pending[..];
+        Some(Frame::split_checked(pending, tag, body_len, config).map(|(frame, _)| frame))
+    }
+
+    fn fail(&mut self, err: FrameError) -> FrameError {
+        self.decoder.pending.clear();
+        self.decoder.consumed = 0;
+        self.chunk = &[];
+        err
+    }
+}
+
+impl Drop for Decode<'_, '_> {
+    fn drop(&mut self) {
+        self.decoder.compact();
+        self.decoder.pending.extend_from_slice(self.chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use zerocopy::AsBytes;
+
+    use crate::header::{Header, StreamId, Tag};
+
+    type Parsed = (Tag, u32, Vec<u8>);
+
+    /// Wire image of a few frames, and the frames it holds.
+    fn wire() -> (Vec<u8>, Vec<Parsed>) {
+        let mut bytes = Vec::new();
+        let mut frames = Vec::new();
+        for (id, body) in [(1, &b"hello"[..]), (3, &[7; 300][..]), (1, &b""[..])] {
+            bytes.extend_from_slice(Header::data(StreamId::new(id), body.len() as u32).as_bytes());
+            bytes.extend_from_slice(body);
+            frames.push((Tag::Data, id, body.to_vec()));
+        }
+        bytes.extend_from_slice(Header::ping(42).with_syn().as_bytes());
+        frames.push((Tag::Ping, 0, vec![]));
+        (bytes, frames)
+    }
+
+    fn parsed(frame: &Frame<&[u8], Untyped>) -> Parsed {
+        (
+            frame.tag(),
+            frame.header().stream_id().val(),
+            frame.body().to_vec(),
+        )
+    }
+
+    #[test]
+    fn every_chunk_size() {
+        let (bytes, expected) = wire();
+        for size in 1..=bytes.len() {
+            let mut decoder = Decoder::new();
+            let mut frames = Vec::new();
+            for chunk in bytes.chunks(size) {
+                let mut decode = decoder.decode(chunk);
+                while let Some(frame) = decode.next_frame() {
+                    frames.push(parsed(&frame.unwrap()));
+                }
+            }
+            assert_eq!(frames, expected, "chunks of {size} bytes");
+            assert_eq!(decoder.pending(), 0, "chunks of {size} bytes");
+        }
+    }
+
+    #[test]
+    fn dropped_decode() {
+        let (bytes, expected) = wire();
+        let mut decoder = Decoder::new();
+        let first = {
+            let mut decode = decoder.decode(&bytes);
+            let frame = decode.next_frame().unwrap().unwrap();
+            (parsed(&frame), frame.encoded_len())
+        };
+        assert_eq!(first.0, expected[0]);
+        assert_eq!(decoder.pending(), bytes.len() - first.1);
+
+        let mut decode = decoder.decode(&[]);
+        let mut frames = Vec::new();
+        while let Some(frame) = decode.next_frame() {
+            frames.push(parsed(&frame.unwrap()));
+        }
+        drop(decode);
+        assert_eq!(frames, expected[1..]);
+        assert_eq!(decoder.pending(), 0);
+    }
+
+    #[test]
+    fn bad_frame_clears_buffer() {
+        let (mut bytes, _) = wire();
+        bytes[1] = 0xff;
+        let mut decoder = Decoder::new();
+        // Buffered while waiting for the rest of the header.
+        assert!(decoder.decode(&bytes[..4]).next_frame().is_none());
+        assert_eq!(decoder.pending(), 4);
+        let mut decode = decoder.decode(&bytes[4..]);
+        assert_eq!(
+            decode.next_frame().unwrap().unwrap_err(),
+            FrameError::UnknownTag(0xff)
+        );
+        assert!(decode.next_frame().is_none());
+        drop(decode);
+        assert_eq!(decoder.pending(), 0);
+
+        // Same when the bad frame is borrowed from the chunk.
+        assert!(decoder.decode(&bytes).next_frame().unwrap().is_err());
+        assert_eq!(decoder.pending(), 0);
+    }
+
+    #[test]
+    fn straddling_frame_copied_once() {
+        let (bytes, expected) = wire();
+        let first = HEADER_LEN + 5;
+        let second = HEADER_LEN + 300;
+        let mut decoder = Decoder::new();
+        let (head, tail) = bytes.split_at(first + 100);
+        {
+            let mut decode = decoder.decode(head);
+            assert_eq!(parsed(&decode.next_frame().unwrap().unwrap()), expected[0]);
+            assert!(decode.next_frame().is_none());
+        }
+        assert_eq!(decoder.pending(), 100);
+
+        let mut decode = decoder.decode(tail);
+        let frame = decode.next_frame().unwrap().unwrap();
+        assert_eq!(parsed(&frame), expected[1]);
+        // Only the missing bytes of the frame were copied from the chunk.
+        assert_eq!(decode.decoder.pending.len(), second);
+        let frame = decode.next_frame().unwrap().unwrap();
+        assert_eq!(parsed(&frame), expected[2]);
+        // The frames that follow are borrowed from the chunk.
+        assert!(tail
+            .as_ptr_range()
+            .contains(&frame.header().as_bytes().as_ptr()));
+    }
+}
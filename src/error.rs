@@ -0,0 +1,121 @@
+//! Errors raised by the codec.
+
+use core::error::Error;
+use core::fmt::{self, Display};
+
+use crate::flags::Flags;
+use crate::frame::MAX_BODY_LEN;
+use crate::header::{GoAwayCode, Tag};
+
+/// Error raised while parsing or validating a frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The buffer ends before the frame does.
+    Incomplete {
+        /// Minimum number of bytes still missing.
+        needed: usize,
+    },
+    /// The buffer is shorter than a frame header.
+    Truncated,
+    /// The header carries a tag the protocol does not define.
+    UnknownTag(u8),
+    /// The header carries a protocol version we do not speak.
+    UnsupportedVersion(u8),
+    /// The header carries flag bits the protocol does not define.
+    InvalidFlags(u16),
+    /// The body of a Data frame differs in size from the one announced by
+    /// the header.
+    BodyLengthMismatch {
+        /// Body size announced by the header.
+        expected: usize,
+        /// Body size found in the buffer.
+        actual: usize,
+    },
+    /// The header announces a body larger than [`MAX_BODY_LEN`].
+    FrameTooLarge(u32),
+    /// A Data or WindowUpdate frame targets the session instead of a stream.
+    MissingStreamId(Tag),
+    /// A Ping or GoAway frame targets a stream instead of the session.
+    UnexpectedStreamId {
+        /// Tag of the frame.
+        tag: Tag,
+        /// Stream the frame targets.
+        stream_id: u32,
+    },
+    /// A frame other than Data carries a body.
+    UnexpectedBody {
+        /// Tag of the frame.
+        tag: Tag,
+        /// Size of the body.
+        len: usize,
+    },
+    /// The flags are not allowed on this kind of frame.
+    FlagsNotAllowed {
+        /// Tag of the frame.
+        tag: Tag,
+        /// Flags set on the frame.
+        flags: Flags,
+    },
+    /// The buffer is too small to hold the frame being written.
+    BufferTooSmall {
+        /// Size of the frame, header included.
+        needed: usize,
+    },
+    /// The frame was parsed as one kind but its tag names another.
+    TagMismatch {
+        /// Tag of the kind the frame was parsed as.
+        expected: Tag,
+        /// Tag found in the header.
+        actual: Tag,
+    },
+}
+
+impl FrameError {
+    /// Code of the GoAway frame terminating a session after this error.
+    pub fn go_away_code(&self) -> GoAwayCode {
+        GoAwayCode::ProtocolError
+    }
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Incomplete { needed } => {
+                write!(f, "incomplete frame, at least {needed} more bytes needed")
+            }
+            FrameError::Truncated => f.write_str("buffer shorter than a frame header"),
+            FrameError::UnknownTag(tag) => write!(f, "unknown frame tag {tag}"),
+            FrameError::UnsupportedVersion(version) => {
+                write!(f, "unsupported protocol version {version}")
+            }
+            FrameError::InvalidFlags(bits) => write!(f, "undefined flag bits {bits:#x}"),
+            FrameError::BodyLengthMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "frame body is {actual} bytes, header announces {expected}"
+                )
+            }
+            FrameError::FrameTooLarge(len) => {
+                write!(f, "frame body of {len} bytes exceeds {MAX_BODY_LEN}")
+            }
+            FrameError::MissingStreamId(tag) => write!(f, "{tag:?} frame on stream 0"),
+            FrameError::UnexpectedStreamId { tag, stream_id } => {
+                write!(f, "{tag:?} frame on stream {stream_id} instead of 0")
+            }
+            FrameError::UnexpectedBody { tag, len } => {
+                write!(f, "{tag:?} frame carries a {len} bytes body")
+            }
+            FrameError::FlagsNotAllowed { tag, flags } => {
+                write!(f, "flags {flags} not allowed on {tag:?} frame")
+            }
+            FrameError::BufferTooSmall { needed } => {
+                write!(f, "buffer too small for a {needed} bytes frame")
+            }
+            FrameError::TagMismatch { expected, actual } => {
+                write!(f, "expected {expected:?} frame, got {actual:?}")
+            }
+        }
+    }
+}
+
+impl Error for FrameError {}
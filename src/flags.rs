@@ -0,0 +1,170 @@
+//! Flags carried by every frame header.
+
+use core::fmt::{self, Debug, Display};
+use core::ops::{BitOr, BitOrAssign};
+use zerocopy::byteorder::network_endian::U16;
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
+
+/// Set of flags carried by a frame header.
+#[derive(Copy, Clone, PartialEq, Eq, FromZeroes, FromBytes, AsBytes)]
+#[repr(C)]
+pub struct Flags(U16);
+
+impl Flags {
+    /// Signals the start of a new stream.
+    pub const SYN: Flags = Flags(U16::from_bytes([0, 1]));
+    /// Acknowledges the start of a new stream.
+    pub const ACK: Flags = Flags(U16::from_bytes([0, 2]));
+    /// Performs a half-close of a stream.
+    pub const FIN: Flags = Flags(U16::from_bytes([0, 4]));
+    /// Resets a stream immediately.
+    pub const RST: Flags = Flags(U16::from_bytes([0, 8]));
+
+    const NAMED: [(Flags, &'static str); 4] = [
+        (Flags::SYN, "SYN"),
+        (Flags::ACK, "ACK"),
+        (Flags::FIN, "FIN"),
+        (Flags::RST, "RST"),
+    ];
+    const KNOWN_BITS: u16 = 0x000f;
+
+    /// No flags set.
+    pub fn empty() -> Self {
+        Flags(U16::ZERO)
+    }
+
+    /// Build flags from raw bits, rejecting bits the protocol does not define.
+    pub fn from_bits(bits: u16) -> Option<Self> {
+        if bits & !Self::KNOWN_BITS == 0 {
+            Some(Flags(bits.into()))
+        } else {
+            None
+        }
+    }
+
+    /// Build flags from raw bits, keeping bits the protocol does not define.
+    pub fn from_bits_retain(bits: u16) -> Self {
+        Flags(bits.into())
+    }
+
+    /// Raw bits, as carried on the wire.
+    pub fn bits(self) -> u16 {
+        self.0.get()
+    }
+
+    /// Bits set that the protocol does not define.
+    pub fn unknown_bits(self) -> u16 {
+        self.bits() & !Self::KNOWN_BITS
+    }
+
+    /// Whether no flag is set.
+    pub fn is_empty(self) -> bool {
+        self.bits() == 0
+    }
+
+    /// Whether all flags set in `other` are also set in `self`.
+    pub fn contains(self, other: Flags) -> bool {
+        self.bits() & other.bits() == other.bits()
+    }
+
+    /// Set the flags set in `other`.
+    pub fn insert(&mut self, other: Flags) {
+        self.0 = (self.bits() | other.bits()).into();
+    }
+
+    /// Clear the flags set in `other`.
+    pub fn remove(&mut self, other: Flags) {
+        self.0 = (self.bits() & !other.bits()).into();
+    }
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Flags::empty()
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(mut self, rhs: Flags) -> Flags {
+        self.insert(rhs);
+        self
+    }
+}
+
+impl BitOrAssign for Flags {
+    fn bitor_assign(&mut self, rhs: Flags) {
+        self.insert(rhs);
+    }
+}
+
+impl Display for Flags {
+    /// Formats set flags as `SYN|ACK`, unknown bits in hex and no flags as `0`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("0");
+        }
+        let mut sep = "";
+        for (flag, name) in Self::NAMED {
+            if self.contains(flag) {
+                write!(f, "{sep}{name}")?;
+                sep = "|";
+            }
+        }
+        if self.unknown_bits() != 0 {
+            write!(f, "{sep}{:#x}", self.unknown_bits())?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Flags({self})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_test() {
+        let mut flags = Flags::SYN | Flags::ACK;
+        assert!(flags.contains(Flags::SYN));
+        assert!(flags.contains(Flags::SYN | Flags::ACK));
+        assert!(!flags.contains(Flags::FIN));
+        flags.insert(Flags::FIN);
+        flags.remove(Flags::SYN);
+        assert_eq!(flags, Flags::ACK | Flags::FIN);
+        assert_eq!(flags.bits(), 6);
+        assert!(Flags::empty().is_empty());
+        assert!(flags.contains(Flags::empty()));
+    }
+
+    #[test]
+    fn unknown_bits() {
+        assert_eq!(
+            Flags::from_bits(0x0f),
+            Some(Flags::SYN | Flags::ACK | Flags::FIN | Flags::RST)
+        );
+        assert_eq!(Flags::from_bits(0x11), None);
+        let flags = Flags::from_bits_retain(0x11);
+        assert_eq!(flags.unknown_bits(), 0x10);
+        assert!(flags.contains(Flags::SYN));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display() {
+        use alloc::string::ToString;
+
+        assert_eq!((Flags::SYN | Flags::ACK).to_string(), "SYN|ACK");
+        assert_eq!(Flags::RST.to_string(), "RST");
+        assert_eq!(Flags::empty().to_string(), "0");
+        assert_eq!(Flags::from_bits_retain(0x30).to_string(), "0x30");
+        assert_eq!(Flags::from_bits_retain(0x14).to_string(), "FIN|0x10");
+        assert_eq!(alloc::format!("{:?}", Flags::SYN), "Flags(SYN)");
+    }
+}
@@ -0,0 +1,809 @@
+//! Frames borrowed from byte buffers.
+
+use core::iter::FusedIterator;
+use zerocopy::{AsBytes, ByteSlice, ByteSliceMut, FromBytes, Ref};
+
+use crate::error::FrameError;
+use crate::flags::Flags;
+use crate::header::{
+    Data, FrameKind, GoAway, Header, Len, Marker, Ping, Tag, Untyped, Version, VersionPolicy,
+    WindowUpdate, HEADER_LEN,
+};
+
+/// Largest Data frame body accepted by the parser.
+pub const MAX_BODY_LEN: u32 = 16 * 1024 * 1024;
+
+/// Rules applied to incoming frames while parsing them.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ParseConfig {
+    pub(crate) version_policy: VersionPolicy,
+    pub(crate) validate: bool,
+}
+
+impl ParseConfig {
+    /// Configuration that also runs [`Frame::validate`] on parsed frames.
+    pub fn strict() -> Self {
+        ParseConfig::default().with_validation(true)
+    }
+
+    /// Whether to run [`Frame::validate`] on parsed frames.
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Protocol versions to accept, [`VersionPolicy::Strict`] by default.
+    pub fn with_version_policy(mut self, version_policy: VersionPolicy) -> Self {
+        self.version_policy = version_policy;
+        self
+    }
+}
+
+/// Frame borrowed from a byte buffer `B`, of the kind named by `T`.
+#[derive(Debug)]
+pub struct Frame<B: ByteSlice, T: Marker> {
+    header: Ref<B, Header<T>>,
+    body: B,
+    // Checked against the header when parsing, so it is always valid.
+    tag: Tag,
+}
+
+impl<B: ByteSlice, T: Marker> Frame<B, T> {
+    /// Parse `bytes` as exactly one frame.
+    pub fn parse(bytes: B) -> Result<Frame<B, T>, FrameError> {
+        Self::parse_with(bytes, ParseConfig::default())
+    }
+
+    /// Parse `bytes` as exactly one frame, following `config`.
+    pub fn parse_with(bytes: B, config: ParseConfig) -> Result<Frame<B, T>, FrameError> {
+        let (header, body) =
+            Ref::<B, Header<T>>::new_from_prefix(bytes).ok_or(FrameError::Truncated)?;
+        let (tag, expected) = header.check(config)?;
+        check_body_len(tag, expected, body.len())?;
+        let frame = Frame { header, body, tag };
+        if config.validate {
+            frame.validate()?;
+        }
+        Ok(frame)
+    }
+
+    /// Parse the frame at the front of `bytes`, returning it along with the
+    /// bytes that follow it.
+    ///
+    /// Only Data frames carry a body, whose size is given by the header
+    /// length; the length of other frames has a tag-specific meaning.
+    pub fn parse_prefix(bytes: B) -> Result<(Frame<B, T>, B), FrameError> {
+        Self::parse_prefix_with(bytes, ParseConfig::default())
+    }
+
+    /// Parse the frame at the front of `bytes` following `config`, returning
+    /// it along with the bytes that follow it.
+    pub fn parse_prefix_with(
+        bytes: B,
+        config: ParseConfig,
+    ) -> Result<(Frame<B, T>, B), FrameError> {
+        let (tag, body_len) = check_prefix::<T>(&bytes, config)?;
+        Self::split_checked(bytes, tag, body_len, config)
+    }
+
+    /// Split the frame at the front of `bytes`, found by [`check_prefix`] to
+    /// carry `tag` and a body of `body_len` bytes, from the bytes that follow
+    /// it.
+    pub(crate) fn split_checked(
+        bytes: B,
+        tag: Tag,
+        body_len: usize,
+        config: ParseConfig,
+    ) -> Result<(Frame<B, T>, B), FrameError> {
+        let (header, rest) = Ref::<B, Header<T>>::new_from_prefix(bytes).expect("header checked");
+        let (body, rest) = rest.split_at(body_len);
+        let frame = Frame { header, body, tag };
+        if config.validate {
+            frame.validate()?;
+        }
+        Ok((frame, rest))
+    }
+
+    /// Check the frame against the rules the protocol sets for its tag.
+    pub fn validate(&self) -> Result<(), FrameError> {
+        let tag = self.tag;
+        let stream_id = self.header.stream_id().val();
+        match tag {
+            Tag::Data | Tag::WindowUpdate if stream_id == 0 => {
+                return Err(FrameError::MissingStreamId(tag));
+            }
+            Tag::Ping | Tag::GoAway if stream_id != 0 => {
+                return Err(FrameError::UnexpectedStreamId { tag, stream_id });
+            }
+            _ => {}
+        }
+        let flags = self.header.flags();
+        if tag == Tag::Ping && !(Flags::SYN | Flags::ACK).contains(flags) {
+            return Err(FrameError::FlagsNotAllowed { tag, flags });
+        }
+        Ok(())
+    }
+
+    /// Size of the frame on the wire, header included.
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + self.body.len()
+    }
+
+    /// Protocol version of the frame.
+    pub fn version(&self) -> Version {
+        self.header.version()
+    }
+
+    /// Tag of the frame, known to be valid since the frame was parsed.
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// Header of the frame, borrowed from the buffer.
+    pub fn header(&self) -> &Header<T> {
+        &self.header
+    }
+
+    /// Body of the frame, only ever non-empty for Data frames.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Raw `length` field of the header, whose meaning depends on the tag.
+    pub fn length(&self) -> Len {
+        self.header.length
+    }
+}
+
+/// Check the header at the front of `bytes` following `config`, returning
+/// the tag and body length it announces.
+pub(crate) fn check_header<T: Marker>(
+    bytes: &[u8],
+    config: ParseConfig,
+) -> Result<(Tag, usize), FrameError> {
+    let (header, _) =
+        Ref::<&[u8], Header<T>>::new_from_prefix(bytes).ok_or_else(|| FrameError::Incomplete {
+            needed: HEADER_LEN - bytes.len(),
+        })?;
+    header.check(config)
+}
+
+/// Like [`check_header`], once `bytes` holds the whole frame.
+pub(crate) fn check_prefix<T: Marker>(
+    bytes: &[u8],
+    config: ParseConfig,
+) -> Result<(Tag, usize), FrameError> {
+    let (tag, body_len) = check_header::<T>(bytes, config)?;
+    let available = bytes.len() - HEADER_LEN;
+    if available < body_len {
+        return Err(FrameError::Incomplete {
+            needed: body_len - available,
+        });
+    }
+    Ok((tag, body_len))
+}
+
+/// Check that a `tag` frame whose header announces an `expected` bytes body
+/// comes with `actual` bytes of body.
+pub(crate) fn check_body_len(tag: Tag, expected: usize, actual: usize) -> Result<(), FrameError> {
+    if actual == expected {
+        return Ok(());
+    }
+    Err(match tag {
+        Tag::Data => FrameError::BodyLengthMismatch { expected, actual },
+        _ => FrameError::UnexpectedBody { tag, len: actual },
+    })
+}
+
+/// Total length of the frame at the front of `bytes`, header included.
+pub fn frame_len(bytes: &[u8]) -> Result<usize, FrameError> {
+    let (frame, _) = Frame::<&[u8], Untyped>::parse_prefix(bytes)?;
+    Ok(frame.encoded_len())
+}
+
+/// Iterator over the frames held in a single buffer.
+///
+/// Frames borrow from the buffer. Iteration stops at a trailing partial frame,
+/// which starts at [`Frames::offset`], so the caller can move it to the front
+/// of the buffer before reading more.
+pub struct Frames<B: ByteSlice> {
+    rest: Option<B>,
+    offset: usize,
+    config: ParseConfig,
+}
+
+impl<B: ByteSlice> Frames<B> {
+    /// Iterate over the frames in `bytes`.
+    pub fn new(bytes: B) -> Self {
+        Self::with_config(bytes, ParseConfig::default())
+    }
+
+    /// Iterate over the frames in `bytes`, parsed following `config`.
+    pub fn with_config(bytes: B, config: ParseConfig) -> Self {
+        Frames {
+            rest: Some(bytes),
+            offset: 0,
+            config,
+        }
+    }
+
+    /// Offset of the first byte not yet consumed as part of a frame.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<B: ByteSlice> Iterator for Frames<B> {
+    type Item = Result<Frame<B, Untyped>, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest.take()?;
+        let (tag, body_len) = match check_prefix::<Untyped>(&rest, self.config) {
+            Ok(checked) => checked,
+            Err(FrameError::Incomplete { .. }) => {
+                self.rest = Some(rest);
+                return None;
+            }
+            Err(err) => return Some(Err(err)),
+        };
+        match Frame::split_checked(rest, tag, body_len, self.config) {
+            Ok((frame, rest)) => {
+                self.rest = Some(rest);
+                self.offset += HEADER_LEN + body_len;
+                Some(Ok(frame))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+impl<B: ByteSlice> FusedIterator for Frames<B> {}
+
+impl<B: ByteSlice> Frame<B, WindowUpdate> {
+    /// Number of bytes the receive window is grown by.
+    pub fn delta(&self) -> u32 {
+        self.header.delta()
+    }
+}
+
+impl<B: ByteSlice> Frame<B, Ping> {
+    /// Opaque value echoed back by the peer.
+    pub fn opaque(&self) -> u32 {
+        self.header.opaque()
+    }
+}
+
+impl<B: ByteSlice> Frame<B, GoAway> {
+    /// Reason the session is being terminated.
+    pub fn code(&self) -> u32 {
+        self.header.code()
+    }
+}
+
+/// A frame of any kind, typed according to the tag found on the wire.
+#[derive(Debug)]
+pub enum AnyFrame<B: ByteSlice> {
+    /// A Data frame.
+    Data(Frame<B, Data>),
+    /// A WindowUpdate frame.
+    WindowUpdate(Frame<B, WindowUpdate>),
+    /// A Ping frame.
+    Ping(Frame<B, Ping>),
+    /// A GoAway frame.
+    GoAway(Frame<B, GoAway>),
+}
+
+impl<B: ByteSlice> AnyFrame<B> {
+    /// Parse `bytes` as exactly one frame.
+    pub fn parse(bytes: B) -> Result<Self, FrameError> {
+        Self::parse_with(bytes, ParseConfig::default())
+    }
+
+    /// Parse `bytes` as exactly one frame, following `config`.
+    pub fn parse_with(bytes: B, config: ParseConfig) -> Result<Self, FrameError> {
+        let header = Header::<Untyped>::read_from_prefix(&bytes).ok_or(FrameError::Truncated)?;
+        Ok(match header.tag()? {
+            Tag::Data => AnyFrame::Data(Frame::parse_with(bytes, config)?),
+            Tag::WindowUpdate => AnyFrame::WindowUpdate(Frame::parse_with(bytes, config)?),
+            Tag::Ping => AnyFrame::Ping(Frame::parse_with(bytes, config)?),
+            Tag::GoAway => AnyFrame::GoAway(Frame::parse_with(bytes, config)?),
+        })
+    }
+
+    /// Parse the frame at the front of `bytes`, returning it along with the
+    /// bytes that follow it.
+    pub fn parse_prefix(bytes: B) -> Result<(Self, B), FrameError> {
+        Self::parse_prefix_with(bytes, ParseConfig::default())
+    }
+
+    /// Parse the frame at the front of `bytes` following `config`, returning
+    /// it along with the bytes that follow it.
+    pub fn parse_prefix_with(bytes: B, config: ParseConfig) -> Result<(Self, B), FrameError> {
+        let header =
+            Header::<Untyped>::read_from_prefix(&bytes).ok_or_else(|| FrameError::Incomplete {
+                needed: HEADER_LEN - bytes.len(),
+            })?;
+        Ok(match header.tag()? {
+            Tag::Data => {
+                let (frame, rest) = Frame::parse_prefix_with(bytes, config)?;
+                (AnyFrame::Data(frame), rest)
+            }
+            Tag::WindowUpdate => {
+                let (frame, rest) = Frame::parse_prefix_with(bytes, config)?;
+                (AnyFrame::WindowUpdate(frame), rest)
+            }
+            Tag::Ping => {
+                let (frame, rest) = Frame::parse_prefix_with(bytes, config)?;
+                (AnyFrame::Ping(frame), rest)
+            }
+            Tag::GoAway => {
+                let (frame, rest) = Frame::parse_prefix_with(bytes, config)?;
+                (AnyFrame::GoAway(frame), rest)
+            }
+        })
+    }
+
+    /// Tag of the frame.
+    pub fn tag(&self) -> Tag {
+        match self {
+            AnyFrame::Data(_) => Tag::Data,
+            AnyFrame::WindowUpdate(_) => Tag::WindowUpdate,
+            AnyFrame::Ping(_) => Tag::Ping,
+            AnyFrame::GoAway(_) => Tag::GoAway,
+        }
+    }
+}
+
+impl<B: ByteSliceMut, T: Marker> Frame<B, T> {
+    /// Lay `header` in place at the front of `buf`, followed by `body_len`
+    /// bytes of body, returning the frame along with the bytes that follow it.
+    ///
+    /// The body is left as found in `buf` and is meant to be filled through
+    /// [`Frame::body_mut`]; [`Frame::encoded_len`] gives the number of bytes
+    /// the frame takes in `buf`.
+    pub fn new_in(buf: B, header: Header<T>, body_len: usize) -> Result<(Self, B), FrameError> {
+        let config = ParseConfig::default().with_version_policy(VersionPolicy::AcceptAny);
+        let (tag, expected) = header.check(config)?;
+        check_body_len(tag, expected, body_len)?;
+        let needed = HEADER_LEN + body_len;
+        if buf.len() < needed {
+            return Err(FrameError::BufferTooSmall { needed });
+        }
+        let (mut header_ref, rest) =
+            Ref::<B, Header<T>>::new_from_prefix(buf).expect("buffer holds at least a header");
+        header_ref.write(header);
+        let (body, rest) = rest.split_at(body_len);
+        Ok((
+            Frame {
+                header: header_ref,
+                body,
+                tag,
+            },
+            rest,
+        ))
+    }
+}
+
+impl<B: ByteSliceMut> Frame<B, Data> {
+    /// Data carried by the frame, writable in place.
+    pub fn body_mut(&mut self) -> &mut [u8] {
+        &mut self.body
+    }
+}
+
+impl<B: ByteSliceMut> Frame<B, Untyped> {
+    /// Rewrite the tag of the frame in place.
+    pub fn set_tag(&mut self, tag: Tag) {
+        self.header.tag = tag as u8;
+        self.tag = tag;
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: Marker> Frame<&'a [u8], T> {
+    /// Frame viewing `header` and `body`, already checked against each other
+    /// and found to carry `tag`.
+    pub(crate) fn from_parts(header: &'a Header<T>, body: &'a [u8], tag: Tag) -> Self {
+        Frame {
+            header: Ref::new(header.as_bytes()).expect("headers are unaligned"),
+            body,
+            tag,
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for &[u8] {}
+    impl Sealed for &mut [u8] {}
+}
+
+/// Byte slices in which a frame header can be re-typed, see [`Frame::cast`].
+///
+/// This trait is sealed: it is implemented for `&[u8]` and `&mut [u8]` only.
+pub trait Castable: ByteSlice + sealed::Sealed {
+    /// Re-type an untyped header borrowed from `Self`, keeping the same bytes.
+    fn cast_header<U>(header: Ref<Self, Header<Untyped>>) -> Ref<Self, Header<U>>;
+}
+
+impl Castable for &[u8] {
+    fn cast_header<U>(header: Ref<Self, Header<Untyped>>) -> Ref<Self, Header<U>> {
+        Ref::new(header.into_ref().as_bytes()).expect("headers share the same layout")
+    }
+}
+
+impl Castable for &mut [u8] {
+    fn cast_header<U>(header: Ref<Self, Header<Untyped>>) -> Ref<Self, Header<U>> {
+        Ref::new(header.into_mut().as_bytes_mut()).expect("headers share the same layout")
+    }
+}
+
+impl<B: Castable> Frame<B, Untyped> {
+    /// Turn the frame into a frame of kind `T`, or hand it back if its tag is
+    /// not `T::TAG`.
+    pub fn cast<T: FrameKind>(self) -> Result<Frame<B, T>, Self> {
+        if self.tag != T::TAG {
+            return Err(self);
+        }
+        Ok(Frame {
+            header: B::cast_header(self.header),
+            body: self.body,
+            tag: self.tag,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::{GoAwayCode, StreamId};
+
+    /// Write a Data frame carrying `body` on stream 1 at the front of `buf`,
+    /// returning its size.
+    fn put_data(buf: &mut [u8], body: &[u8]) -> usize {
+        let header = Header::data(StreamId::new(1), body.len() as u32);
+        buf[..HEADER_LEN].copy_from_slice(header.as_bytes());
+        buf[HEADER_LEN..HEADER_LEN + body.len()].copy_from_slice(body);
+        HEADER_LEN + body.len()
+    }
+
+    #[test]
+    fn frames() {
+        let mut buf = [0; 64];
+        let mut len = put_data(&mut buf, b"hello");
+        buf[len..len + HEADER_LEN].copy_from_slice(Header::ping(7).with_syn().as_bytes());
+        len += HEADER_LEN;
+        let mut frames = Frames::new(&buf[..len]);
+        let frame = frames.next().unwrap().unwrap();
+        assert_eq!((frame.tag(), frame.body()), (Tag::Data, &b"hello"[..]));
+        assert_eq!(frames.offset(), HEADER_LEN + 5);
+        assert_eq!(frames.next().unwrap().unwrap().tag(), Tag::Ping);
+        assert!(frames.next().is_none());
+        assert_eq!(frames.offset(), len);
+    }
+
+    #[test]
+    fn trailing_partial_frame() {
+        let mut buf = [0; 64];
+        let first = put_data(&mut buf, b"hello");
+        let len = first + put_data(&mut buf[first..], b"world");
+        // Partial body, then partial header.
+        for end in [len - 3, first + 3] {
+            let mut frames = Frames::new(&buf[..end]);
+            assert!(frames.next().unwrap().is_ok());
+            assert!(frames.next().is_none());
+            assert_eq!(frames.offset(), first);
+        }
+    }
+
+    #[test]
+    fn fused_after_error() {
+        let mut buf = [0; 64];
+        let first = put_data(&mut buf, b"hello");
+        let len = first + put_data(&mut buf[first..], b"world");
+        buf[1] = 0xff;
+        let mut frames = Frames::new(&buf[..len]);
+        assert_eq!(
+            frames.next().unwrap().unwrap_err(),
+            FrameError::UnknownTag(0xff)
+        );
+        assert!(frames.next().is_none());
+        assert!(frames.next().is_none());
+        assert_eq!(frames.offset(), 0);
+    }
+
+    #[test]
+    fn parse_prefix() {
+        let mut buf = [0; 64];
+        let first = put_data(&mut buf, b"hello");
+        buf[first..first + HEADER_LEN]
+            .copy_from_slice(Header::window_update(StreamId::new(1), 9).as_bytes());
+        let len = first + HEADER_LEN + 2;
+
+        let (frame, rest) = Frame::<_, Untyped>::parse_prefix(&buf[..len]).unwrap();
+        assert_eq!(frame.body(), b"hello");
+        assert_eq!(rest.len(), HEADER_LEN + 2);
+        // The length of a WindowUpdate is its delta, not a body size.
+        let (frame, rest) = Frame::<_, WindowUpdate>::parse_prefix(rest).unwrap();
+        assert_eq!(frame.delta(), 9);
+        assert!(frame.body().is_empty());
+        assert_eq!(rest, &buf[first + HEADER_LEN..len]);
+    }
+
+    #[test]
+    fn parse_prefix_incomplete() {
+        let mut buf = [0; 64];
+        let len = put_data(&mut buf, b"hello");
+        assert_eq!(
+            Frame::<_, Untyped>::parse_prefix(&buf[..5]).unwrap_err(),
+            FrameError::Incomplete {
+                needed: HEADER_LEN - 5
+            }
+        );
+        assert_eq!(
+            Frame::<_, Untyped>::parse_prefix(&buf[..len - 2]).unwrap_err(),
+            FrameError::Incomplete { needed: 2 }
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut buf = [0; 64];
+        let len = put_data(&mut buf, b"hello");
+        let parse = |bytes: &[u8]| Frame::<_, Untyped>::parse(bytes).map(|_| ());
+
+        assert_eq!(parse(&buf[..HEADER_LEN - 1]), Err(FrameError::Truncated));
+        assert_eq!(
+            parse(&buf[..len - 1]),
+            Err(FrameError::BodyLengthMismatch {
+                expected: 5,
+                actual: 4
+            })
+        );
+
+        let mut bytes = buf;
+        bytes[1] = 9;
+        assert_eq!(parse(&bytes[..len]), Err(FrameError::UnknownTag(9)));
+
+        let mut bytes = buf;
+        bytes[3] = 0x20;
+        assert_eq!(parse(&bytes[..len]), Err(FrameError::InvalidFlags(0x20)));
+
+        let mut bytes = buf;
+        bytes[8..HEADER_LEN].copy_from_slice(&(MAX_BODY_LEN + 1).to_be_bytes());
+        assert_eq!(
+            parse(&bytes[..len]),
+            Err(FrameError::FrameTooLarge(MAX_BODY_LEN + 1))
+        );
+    }
+
+    #[test]
+    fn errors_map_to_protocol_error() {
+        for err in [
+            FrameError::Truncated,
+            FrameError::UnknownTag(9),
+            FrameError::InvalidFlags(0x20),
+        ] {
+            assert_eq!(err.go_away_code(), GoAwayCode::ProtocolError);
+        }
+    }
+
+    #[test]
+    fn version_checked() {
+        let mut buf = [0; 64];
+        let len = put_data(&mut buf, b"hello");
+        buf[0] = 1;
+        let bytes = &buf[..len];
+        assert_eq!(
+            Frame::<_, Untyped>::parse(bytes).unwrap_err(),
+            FrameError::UnsupportedVersion(1)
+        );
+        let config = ParseConfig::default().with_version_policy(VersionPolicy::AllowList(&[0, 1]));
+        let frame = Frame::<_, Untyped>::parse_with(bytes, config).unwrap();
+        assert_eq!(frame.version().val(), 1);
+        let config = ParseConfig::default().with_version_policy(VersionPolicy::AcceptAny);
+        assert!(Frame::<_, Untyped>::parse_prefix_with(bytes, config).is_ok());
+    }
+
+    #[test]
+    fn validate() {
+        let strict = |header: Header<Untyped>| {
+            Frame::<_, Untyped>::parse_with(header.as_bytes(), ParseConfig::strict()).map(|_| ())
+        };
+        let stream = StreamId::new(3);
+
+        let ping = Header::ping(1).with_syn().into_untyped();
+        assert_eq!(strict(ping), Ok(()));
+        assert!(Frame::<_, Untyped>::parse(ping.with_fin().as_bytes()).is_ok());
+        assert_eq!(
+            strict(ping.with_fin()),
+            Err(FrameError::FlagsNotAllowed {
+                tag: Tag::Ping,
+                flags: Flags::SYN | Flags::FIN
+            })
+        );
+
+        let mut ping = ping;
+        ping.stream_id = stream;
+        assert_eq!(
+            strict(ping),
+            Err(FrameError::UnexpectedStreamId {
+                tag: Tag::Ping,
+                stream_id: 3
+            })
+        );
+        let mut go_away = Header::go_away(0).into_untyped();
+        go_away.stream_id = stream;
+        assert_eq!(
+            strict(go_away),
+            Err(FrameError::UnexpectedStreamId {
+                tag: Tag::GoAway,
+                stream_id: 3
+            })
+        );
+
+        let data = Header::data(StreamId::SESSION, 0).into_untyped();
+        assert_eq!(strict(data), Err(FrameError::MissingStreamId(Tag::Data)));
+        let update = Header::window_update(StreamId::SESSION, 1).into_untyped();
+        assert_eq!(
+            strict(update),
+            Err(FrameError::MissingStreamId(Tag::WindowUpdate))
+        );
+        let update = Header::window_update(stream, 1).with_ack().into_untyped();
+        assert_eq!(strict(update), Ok(()));
+    }
+
+    #[test]
+    fn unexpected_body() {
+        let mut buf = [0; 64];
+        buf[..HEADER_LEN].copy_from_slice(Header::ping(1).as_bytes());
+        assert_eq!(
+            Frame::<_, Untyped>::parse(&buf[..HEADER_LEN + 2]).unwrap_err(),
+            FrameError::UnexpectedBody {
+                tag: Tag::Ping,
+                len: 2
+            }
+        );
+    }
+
+    #[test]
+    fn cast() {
+        let mut buf = [0; 64];
+        let len = put_data(&mut buf, b"hello");
+        let frame = Frame::<_, Untyped>::parse(&buf[..len]).unwrap();
+        let frame = frame.cast::<Ping>().unwrap_err();
+        assert_eq!(frame.tag(), Tag::Data);
+        let frame = frame.cast::<Data>().unwrap();
+        assert_eq!(frame.body(), b"hello");
+
+        buf[..HEADER_LEN].copy_from_slice(Header::ping(7).as_bytes());
+        let mut frame = Frame::<_, Untyped>::parse(&mut buf[..HEADER_LEN]).unwrap();
+        frame.set_tag(Tag::WindowUpdate);
+        assert_eq!(frame.tag(), Tag::WindowUpdate);
+        let frame = frame.cast::<WindowUpdate>().unwrap();
+        assert_eq!(frame.delta(), 7);
+        assert_eq!(buf[1], Tag::WindowUpdate as u8);
+    }
+
+    #[test]
+    fn typed_parse() {
+        let mut buf = [0; 64];
+        let len = put_data(&mut buf, b"hello");
+        let bytes = &buf[..len];
+        assert_eq!(
+            Frame::<_, Ping>::parse(bytes).unwrap_err(),
+            FrameError::TagMismatch {
+                expected: Tag::Ping,
+                actual: Tag::Data
+            }
+        );
+        assert_eq!(
+            Frame::<_, GoAway>::parse_prefix(bytes).unwrap_err(),
+            FrameError::TagMismatch {
+                expected: Tag::GoAway,
+                actual: Tag::Data
+            }
+        );
+        assert_eq!(Frame::<_, Data>::parse(bytes).unwrap().body(), b"hello");
+
+        let ping = Header::ping(42).with_ack();
+        let frame = Frame::<_, Ping>::parse(ping.as_bytes()).unwrap();
+        assert_eq!(frame.opaque(), 42);
+    }
+
+    #[test]
+    fn any_frame() {
+        let mut buf = [0; 64];
+        let mut len = put_data(&mut buf, b"hello");
+        for header in [
+            Header::window_update(StreamId::new(1), 3).into_untyped(),
+            Header::ping(4).into_untyped(),
+            Header::go_away(1).into_untyped(),
+        ] {
+            buf[len..len + HEADER_LEN].copy_from_slice(header.as_bytes());
+            len += HEADER_LEN;
+        }
+
+        let mut rest = &buf[..len];
+        let mut tags = [None; 4];
+        for tag in &mut tags {
+            let (frame, tail) = AnyFrame::parse_prefix(rest).unwrap();
+            rest = tail;
+            *tag = Some(frame.tag());
+            match frame {
+                AnyFrame::Data(frame) => assert_eq!(frame.body(), b"hello"),
+                AnyFrame::WindowUpdate(frame) => assert_eq!(frame.delta(), 3),
+                AnyFrame::Ping(frame) => assert_eq!(frame.opaque(), 4),
+                AnyFrame::GoAway(frame) => assert_eq!(frame.code(), 1),
+            }
+        }
+        assert!(rest.is_empty());
+        assert_eq!(
+            tags,
+            [Tag::Data, Tag::WindowUpdate, Tag::Ping, Tag::GoAway].map(Some)
+        );
+
+        let ping = Header::ping(4);
+        assert!(matches!(
+            AnyFrame::parse(ping.as_bytes()),
+            Ok(AnyFrame::Ping(_))
+        ));
+        assert_eq!(
+            AnyFrame::parse(&buf[..3]).unwrap_err(),
+            FrameError::Truncated
+        );
+        assert_eq!(
+            AnyFrame::parse_prefix(&buf[..3]).unwrap_err(),
+            FrameError::Incomplete {
+                needed: HEADER_LEN - 3
+            }
+        );
+    }
+
+    #[test]
+    fn new_in() {
+        let mut buf = [0; 64];
+        let header = Header::data(StreamId::new(1), 5).with_fin();
+        let (mut frame, rest) = Frame::new_in(&mut buf[..], header, 5).unwrap();
+        frame.body_mut().copy_from_slice(b"hello");
+        assert_eq!(frame.encoded_len(), HEADER_LEN + 5);
+        assert_eq!(rest.len(), 64 - HEADER_LEN - 5);
+        let (ping, _) = Frame::new_in(rest, Header::ping(9), 0).unwrap();
+        assert_eq!(ping.encoded_len(), HEADER_LEN);
+
+        let mut frames = Frames::new(&buf[..2 * HEADER_LEN + 5]);
+        let frame = frames.next().unwrap().unwrap();
+        assert_eq!(frame.header().flags(), Flags::FIN);
+        assert_eq!(frame.body(), b"hello");
+        assert_eq!(frames.next().unwrap().unwrap().tag(), Tag::Ping);
+    }
+
+    #[test]
+    fn new_in_errors() {
+        let mut buf = [0; 64];
+        let header = Header::data(StreamId::new(1), 60);
+        assert_eq!(
+            Frame::new_in(&mut buf[..], header, 60).unwrap_err(),
+            FrameError::BufferTooSmall {
+                needed: HEADER_LEN + 60
+            }
+        );
+        assert_eq!(
+            Frame::new_in(&mut buf[..], header, 4).unwrap_err(),
+            FrameError::BodyLengthMismatch {
+                expected: 60,
+                actual: 4
+            }
+        );
+        assert_eq!(
+            Frame::new_in(&mut buf[..], Header::ping(1), 4).unwrap_err(),
+            FrameError::UnexpectedBody {
+                tag: Tag::Ping,
+                len: 4
+            }
+        );
+        // Nothing is written on failure.
+        assert_eq!(buf, [0; 64]);
+    }
+}
@@ -0,0 +1,411 @@
+//! Frame header layout and the marker types naming frame kinds.
+
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use zerocopy::byteorder::network_endian::U32;
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
+
+use crate::error::FrameError;
+use crate::flags::Flags;
+use crate::frame::{ParseConfig, MAX_BODY_LEN};
+
+/// Frame header, laid out as on the wire.
+///
+/// The meaning of the `length` field depends on the tag, and is exposed by
+/// the accessors of `Header<T>` for each marker `T`.
+#[derive(Copy, Clone, Debug, FromZeroes, FromBytes, AsBytes)]
+#[repr(C, packed)]
+pub struct Header<T> {
+    pub(crate) version: Version,
+    pub(crate) tag: u8,
+    pub(crate) flags: Flags,
+    pub(crate) stream_id: StreamId,
+    pub(crate) length: Len,
+    _marker: PhantomData<T>,
+}
+
+/// Size of an encoded frame header.
+pub const HEADER_LEN: usize = core::mem::size_of::<Header<Data>>();
+
+/// Reason carried by a GoAway frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum GoAwayCode {
+    /// The session is closed normally.
+    Normal = 0,
+    /// The peer violated the protocol.
+    ProtocolError = 1,
+    /// An internal error occurred.
+    InternalError = 2,
+}
+
+impl From<GoAwayCode> for u32 {
+    fn from(code: GoAwayCode) -> u32 {
+        code as u32
+    }
+}
+
+/// Kind of a frame, as carried by its header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Tag {
+    /// Carries stream data.
+    Data = 0,
+    /// Grows the receive window of a stream.
+    WindowUpdate = 1,
+    /// Measures round-trip time or keeps the session alive.
+    Ping = 2,
+    /// Terminates the session.
+    GoAway = 3,
+}
+
+impl TryFrom<u8> for Tag {
+    type Error = FrameError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Data),
+            1 => Ok(Self::WindowUpdate),
+            2 => Ok(Self::Ping),
+            3 => Ok(Self::GoAway),
+            _ => Err(FrameError::UnknownTag(value)),
+        }
+    }
+}
+
+/// Protocol version carried by a frame header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, FromZeroes, FromBytes, AsBytes)]
+#[repr(C)]
+pub struct Version(u8);
+
+impl Version {
+    /// The protocol version implemented by this crate.
+    pub const CURRENT: Version = Version(0);
+
+    /// Raw version number.
+    pub fn val(self) -> u8 {
+        self.0
+    }
+}
+
+/// Protocol versions accepted when parsing a frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum VersionPolicy {
+    /// Only accept [`Version::CURRENT`].
+    #[default]
+    Strict,
+    /// Accept any version, leaving its interpretation to the caller.
+    AcceptAny,
+    /// Only accept the listed versions.
+    AllowList(&'static [u8]),
+}
+
+impl VersionPolicy {
+    /// Whether frames of `version` are accepted.
+    pub fn allows(self, version: Version) -> bool {
+        match self {
+            VersionPolicy::Strict => version == Version::CURRENT,
+            VersionPolicy::AcceptAny => true,
+            VersionPolicy::AllowList(versions) => versions.contains(&version.val()),
+        }
+    }
+}
+
+/// Raw `length` field of a frame header.
+#[derive(Copy, Clone, Debug, FromZeroes, FromBytes, AsBytes)]
+#[repr(C)]
+pub struct Len(U32);
+
+impl Len {
+    /// Raw field value.
+    pub fn val(self) -> u32 {
+        self.0.get()
+    }
+}
+
+/// Identifier of the stream a frame belongs to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, FromZeroes, FromBytes, AsBytes)]
+#[repr(C)]
+pub struct StreamId(U32);
+
+impl StreamId {
+    /// Stream ID reserved for session-level frames (Ping, GoAway).
+    pub const SESSION: StreamId = StreamId(U32::ZERO);
+
+    /// Stream ID with the raw value `val`.
+    pub fn new(val: u32) -> Self {
+        StreamId(val.into())
+    }
+
+    /// Raw stream ID.
+    pub fn val(self) -> u32 {
+        self.0.get()
+    }
+}
+
+/// Marker for a frame whose kind is only known at runtime, through its tag.
+#[derive(Copy, Clone, Debug)]
+pub struct Untyped {}
+
+/// Marker types a `Header` or `Frame` can be parsed as.
+pub trait Marker: Debug + Copy {
+    /// Tag the frame must carry, or `None` if any tag is accepted.
+    const EXPECTED_TAG: Option<Tag>;
+}
+
+/// Marker types naming the kind of a frame.
+pub trait FrameKind: Debug + Copy {
+    /// Tag of the frames of this kind.
+    const TAG: Tag;
+}
+
+impl<T: FrameKind> Marker for T {
+    const EXPECTED_TAG: Option<Tag> = Some(T::TAG);
+}
+
+impl Marker for Untyped {
+    const EXPECTED_TAG: Option<Tag> = None;
+}
+
+/// Marker for Data frames.
+#[derive(Copy, Clone, Debug)]
+pub struct Data {}
+
+impl FrameKind for Data {
+    const TAG: Tag = Tag::Data;
+}
+
+/// Marker for WindowUpdate frames.
+#[derive(Copy, Clone, Debug)]
+pub struct WindowUpdate {}
+
+impl FrameKind for WindowUpdate {
+    const TAG: Tag = Tag::WindowUpdate;
+}
+
+/// Marker for Ping frames.
+#[derive(Copy, Clone, Debug)]
+pub struct Ping {}
+
+impl FrameKind for Ping {
+    const TAG: Tag = Tag::Ping;
+}
+
+/// Marker for GoAway frames.
+#[derive(Copy, Clone, Debug)]
+pub struct GoAway {}
+
+impl FrameKind for GoAway {
+    const TAG: Tag = Tag::GoAway;
+}
+
+impl<T> Header<T> {
+    fn new(tag: Tag, stream_id: StreamId, length: u32) -> Self {
+        Header {
+            version: Version::CURRENT,
+            tag: tag as u8,
+            flags: Flags::empty(),
+            stream_id,
+            length: Len(length.into()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Forget the kind of the frame, keeping the same fields.
+    pub fn into_untyped(self) -> Header<Untyped> {
+        self.retype()
+    }
+
+    /// Same header, marked as `U`.
+    pub(crate) fn retype<U>(self) -> Header<U> {
+        Header {
+            version: self.version,
+            tag: self.tag,
+            flags: self.flags,
+            stream_id: self.stream_id,
+            length: self.length,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Protocol version of the frame.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Stream the frame belongs to, [`StreamId::SESSION`] for session frames.
+    pub fn stream_id(&self) -> StreamId {
+        self.stream_id
+    }
+
+    /// Tag of the frame, if it is one the protocol defines.
+    pub fn tag(&self) -> Result<Tag, FrameError> {
+        Tag::try_from(self.tag)
+    }
+
+    /// Flags set on the frame.
+    pub fn flags(&self) -> Flags {
+        self.flags
+    }
+
+    /// Add `flags` to the flags already set on the header.
+    pub fn with_flags(mut self, flags: Flags) -> Self {
+        self.flags |= flags;
+        self
+    }
+
+    /// Add the SYN flag.
+    pub fn with_syn(self) -> Self {
+        self.with_flags(Flags::SYN)
+    }
+
+    /// Add the ACK flag.
+    pub fn with_ack(self) -> Self {
+        self.with_flags(Flags::ACK)
+    }
+
+    /// Add the FIN flag.
+    pub fn with_fin(self) -> Self {
+        self.with_flags(Flags::FIN)
+    }
+
+    /// Add the RST flag.
+    pub fn with_rst(self) -> Self {
+        self.with_flags(Flags::RST)
+    }
+}
+
+impl<T: Marker> Header<T> {
+    /// Check the header against `config` and its marker, returning its tag
+    /// and the number of body bytes following it on the wire.
+    pub(crate) fn check(&self, config: ParseConfig) -> Result<(Tag, usize), FrameError> {
+        if !config.version_policy.allows(self.version) {
+            return Err(FrameError::UnsupportedVersion(self.version.val()));
+        }
+        let flags = self.flags;
+        if flags.unknown_bits() != 0 {
+            return Err(FrameError::InvalidFlags(flags.bits()));
+        }
+        let tag = self.tag()?;
+        if let Some(expected) = T::EXPECTED_TAG {
+            if tag != expected {
+                return Err(FrameError::TagMismatch {
+                    expected,
+                    actual: tag,
+                });
+            }
+        }
+        match tag {
+            Tag::Data if self.length.val() > MAX_BODY_LEN => {
+                Err(FrameError::FrameTooLarge(self.length.val()))
+            }
+            Tag::Data => Ok((Tag::Data, self.length.val() as usize)),
+            tag => Ok((tag, 0)),
+        }
+    }
+}
+
+impl Header<Data> {
+    /// Create a new data frame header.
+    pub fn data(id: StreamId, len: u32) -> Self {
+        Header::new(Tag::Data, id, len)
+    }
+
+    /// Length of the data following the header.
+    pub fn length(&self) -> Len {
+        self.length
+    }
+}
+
+impl Header<WindowUpdate> {
+    /// Create a new window update frame header.
+    pub fn window_update(id: StreamId, delta: u32) -> Self {
+        Header::new(Tag::WindowUpdate, id, delta)
+    }
+
+    /// Number of bytes the receive window is grown by.
+    pub fn delta(&self) -> u32 {
+        self.length.val()
+    }
+}
+
+impl Header<Ping> {
+    /// Create a new ping frame header.
+    pub fn ping(opaque: u32) -> Self {
+        Header::new(Tag::Ping, StreamId::SESSION, opaque)
+    }
+
+    /// Opaque value echoed back by the peer.
+    pub fn opaque(&self) -> u32 {
+        self.length.val()
+    }
+}
+
+impl Header<GoAway> {
+    /// Create a new go away frame header.
+    pub fn go_away(code: u32) -> Self {
+        Header::new(Tag::GoAway, StreamId::SESSION, code)
+    }
+
+    /// Reason the session is being terminated.
+    pub fn code(&self) -> u32 {
+        self.length.val()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_headers() {
+        let update = Header::window_update(StreamId::new(3), 1 << 20);
+        assert_eq!(update.tag(), Ok(Tag::WindowUpdate));
+        assert_eq!(update.stream_id(), StreamId::new(3));
+        assert_eq!(update.delta(), 1 << 20);
+
+        let ping = Header::ping(0xdead_beef);
+        assert_eq!(ping.tag(), Ok(Tag::Ping));
+        assert_eq!(ping.stream_id(), StreamId::SESSION);
+        assert_eq!(ping.opaque(), 0xdead_beef);
+
+        let go_away = Header::go_away(GoAwayCode::InternalError.into());
+        assert_eq!(go_away.tag(), Ok(Tag::GoAway));
+        assert_eq!(go_away.stream_id(), StreamId::SESSION);
+        assert_eq!(go_away.code(), 2);
+    }
+
+    #[test]
+    fn wire_layout() {
+        let update = Header::window_update(StreamId::new(3), 0x0102_0304);
+        assert_eq!(
+            update.as_bytes(),
+            [0, 1, 0, 0, 0, 0, 0, 3, 1, 2, 3, 4].as_slice()
+        );
+        let read = Header::<WindowUpdate>::read_from(update.as_bytes()).unwrap();
+        assert_eq!(read.delta(), 0x0102_0304);
+    }
+
+    #[test]
+    fn header_flags() {
+        let header = Header::data(StreamId::new(1), 0).with_syn().with_fin();
+        assert_eq!(header.flags(), Flags::SYN | Flags::FIN);
+        let header = Header::ping(1).with_flags(Flags::ACK);
+        assert_eq!(header.flags(), Flags::ACK);
+        assert_eq!(header.with_rst().flags(), Flags::ACK | Flags::RST);
+    }
+
+    #[test]
+    fn version_policy() {
+        let (v0, v1, v2) = (Version(0), Version(1), Version(2));
+        assert!(VersionPolicy::Strict.allows(v0));
+        assert!(!VersionPolicy::Strict.allows(v1));
+        assert!(VersionPolicy::AcceptAny.allows(v1));
+        assert!(VersionPolicy::AcceptAny.allows(Version(0xff)));
+        let policy = VersionPolicy::AllowList(&[0, 2]);
+        assert!(policy.allows(v0));
+        assert!(!policy.allows(v1));
+        assert!(policy.allows(v2));
+        assert_eq!(VersionPolicy::default(), VersionPolicy::Strict);
+    }
+}
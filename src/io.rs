@@ -0,0 +1,426 @@
+//! Adapters between frames and `std::io`.
+
+use alloc::vec::Vec;
+use std::io::{self, IoSlice, Read, Write};
+use zerocopy::{AsBytes, ByteSlice};
+
+use crate::error::FrameError;
+use crate::frame::{check_prefix, Frame, ParseConfig};
+use crate::header::{Data, Header, Marker, Untyped, HEADER_LEN};
+
+// Minimum number of bytes asked for by each read of a `FrameReader`.
+const READ_CHUNK: usize = 8 * 1024;
+
+impl Header<Data> {
+    /// Header and `payload` as slices for [`Write::write_vectored`], so the
+    /// payload is sent without being copied next to the header.
+    ///
+    /// The header length is expected to match the payload size.
+    pub fn io_slices<'a>(&'a self, payload: &'a [u8]) -> [IoSlice<'a>; 2] {
+        [IoSlice::new(self.as_bytes()), IoSlice::new(payload)]
+    }
+
+    /// Append the header followed by each of `payloads` to `out`, as slices
+    /// for [`Write::write_vectored`].
+    ///
+    /// The header length is expected to match the total payload size.
+    pub fn io_slices_from<'a>(&'a self, payloads: &[&'a [u8]], out: &mut Vec<IoSlice<'a>>) {
+        out.push(IoSlice::new(self.as_bytes()));
+        out.extend(payloads.iter().map(|payload| IoSlice::new(payload)));
+    }
+}
+
+/// Write all of `bufs` with [`Write::write_vectored`], resuming after partial
+/// writes.
+///
+/// `bufs` is advanced past the bytes written, so that a call interrupted by an
+/// error such as [`io::ErrorKind::WouldBlock`] can be retried with what is
+/// left of it.
+pub fn write_all_vectored<W: Write>(
+    writer: &mut W,
+    bufs: &mut &mut [IoSlice<'_>],
+) -> io::Result<()> {
+    // Skip leading empty slices, which would make a successful write look
+    // like a zero-length one.
+    IoSlice::advance_slices(bufs, 0);
+    while !bufs.is_empty() {
+        match writer.write_vectored(bufs) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole frame",
+                ));
+            }
+            Ok(n) => IoSlice::advance_slices(bufs, n),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Write a Data frame made of `header` followed by `payloads`, without copying
+/// the payloads.
+pub fn write_data<W: Write>(
+    writer: &mut W,
+    header: &Header<Data>,
+    payloads: &[&[u8]],
+) -> io::Result<()> {
+    let expected = header.length().val() as usize;
+    let actual = payloads.iter().map(|payload| payload.len()).sum();
+    if expected != actual {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            FrameError::BodyLengthMismatch { expected, actual },
+        ));
+    }
+    let mut slices = Vec::with_capacity(payloads.len() + 1);
+    header.io_slices_from(payloads, &mut slices);
+    write_all_vectored(writer, &mut &mut slices[..])
+}
+
+/// Blocking reader of frames from a [`Read`] stream.
+///
+/// Bytes are read in chunks into a buffer reused across frames, and frames are
+/// handed out borrowed from it one at a time.
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    // Bytes of `buf` read but not yet handed out as frames.
+    start: usize,
+    end: usize,
+    // Size of the frame handed out by the last call to `read_frame`.
+    last: usize,
+    config: ParseConfig,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Reader parsing frames with the default [`ParseConfig`].
+    pub fn new(reader: R) -> Self {
+        Self::with_config(reader, ParseConfig::default())
+    }
+
+    /// Reader parsing frames following `config`.
+    pub fn with_config(reader: R, config: ParseConfig) -> Self {
+        FrameReader {
+            reader,
+            buf: Vec::new(),
+            start: 0,
+            end: 0,
+            last: 0,
+            config,
+        }
+    }
+
+    /// Read the next frame, or `None` if the stream ends before a new frame
+    /// starts.
+    ///
+    /// The frame borrows the reader's buffer until the next call.
+    pub fn read_frame(&mut self) -> io::Result<Option<Frame<&[u8], Untyped>>> {
+        self.start += self.last;
+        self.last = 0;
+        let (tag, body_len) = loop {
+            let available = &self.buf[self.start..self.end];
+            match check_prefix::<Untyped>(available, self.config) {
+                Ok(checked) => break checked,
+                Err(FrameError::Incomplete { needed }) => {
+                    if self.fill(needed)? == 0 {
+                        return if self.start == self.end {
+                            Ok(None)
+                        } else {
+                            Err(io::ErrorKind::UnexpectedEof.into())
+                        };
+                    }
+                }
+                Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+            }
+        };
+        self.last = HEADER_LEN + body_len;
+        let bytes = &self.buf[self.start..self.end];
+        Frame::split_checked(bytes, tag, body_len, self.config)
+            .map(|(frame, _)| Some(frame))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Read at least one byte, with room for `needed` more, returning the
+    /// number of bytes read.
+    fn fill(&mut self, needed: usize) -> io::Result<usize> {
+        if self.buf.len() - self.end < needed {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        if self.buf.len() - self.end < needed {
+            self.buf.resize(self.end + needed.max(READ_CHUNK), 0);
+        }
+        loop {
+            match self.reader.read(&mut self.buf[self.end..]) {
+                Ok(n) => {
+                    self.end += n;
+                    return Ok(n);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Underlying reader, mutably.
+    ///
+    /// Reading from it directly skips bytes the frames are made of.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Give the underlying reader back, dropping buffered bytes.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Blocking writer of frames to a [`Write`] stream.
+///
+/// Each frame is sent with a single vectored write of its header and body,
+/// unless the stream accepts it only partially.
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> FrameWriter<W> {
+    /// Writer sending frames to `writer`.
+    pub fn new(writer: W) -> Self {
+        FrameWriter { writer }
+    }
+
+    /// Write `frame`, header and body.
+    pub fn write_frame<B: ByteSlice, T: Marker>(&mut self, frame: &Frame<B, T>) -> io::Result<()> {
+        let mut slices = [
+            IoSlice::new(frame.header().as_bytes()),
+            IoSlice::new(frame.body()),
+        ];
+        write_all_vectored(&mut self.writer, &mut &mut slices[..])
+    }
+
+    /// Write a frame without body, such as a WindowUpdate, Ping or GoAway.
+    pub fn write_header<T: Marker>(&mut self, header: &Header<T>) -> io::Result<()> {
+        let config = ParseConfig::default();
+        match header.check(config) {
+            Ok((_, 0)) => self.writer.write_all(header.as_bytes()),
+            Ok((_, expected)) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                FrameError::BodyLengthMismatch {
+                    expected,
+                    actual: 0,
+                },
+            )),
+            Err(err) => Err(io::Error::new(io::ErrorKind::InvalidInput, err)),
+        }
+    }
+
+    /// Write a Data frame made of `header` followed by `payloads`, see
+    /// [`write_data`].
+    pub fn write_data(&mut self, header: &Header<Data>, payloads: &[&[u8]]) -> io::Result<()> {
+        write_data(&mut self.writer, header, payloads)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Underlying writer, mutably.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Give the underlying writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::{Header, StreamId, Tag};
+
+    /// Reader handing out a single byte per call.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    /// Writer accepting at most 3 bytes per call, spread over the slices of
+    /// vectored writes, and interrupted every other call.
+    #[derive(Default)]
+    struct Dribble {
+        out: Vec<u8>,
+        calls: usize,
+    }
+
+    impl Write for Dribble {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.write_vectored(&[IoSlice::new(buf)])
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+            self.calls += 1;
+            if self.calls % 2 == 0 {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let mut room = 3;
+            for buf in bufs {
+                let n = buf.len().min(room);
+                self.out.extend_from_slice(&buf[..n]);
+                room -= n;
+            }
+            Ok(3 - room)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Wire image of `count` Data frames, the body of the `i`th one being
+    /// `len` bytes of `i`.
+    fn wire(count: u32, len: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for i in 0..count {
+            bytes.extend_from_slice(Header::data(StreamId::new(2 * i + 1), len as u32).as_bytes());
+            bytes.resize(bytes.len() + len, i as u8);
+        }
+        bytes
+    }
+
+    fn read_all<R: Read>(reader: &mut FrameReader<R>, len: usize) -> io::Result<u32> {
+        let mut count = 0;
+        while let Some(frame) = reader.read_frame()? {
+            assert_eq!(frame.tag(), Tag::Data);
+            assert_eq!(frame.header().stream_id().val(), 2 * count + 1);
+            assert_eq!(frame.body().len(), len);
+            assert!(frame.body().iter().all(|&b| b == count as u8));
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    #[test]
+    fn byte_at_a_time() {
+        // More than a chunk in total, so the buffer gets compacted.
+        let bytes = wire(40, 300);
+        assert!(bytes.len() > READ_CHUNK);
+        let mut reader = FrameReader::new(Trickle(&bytes));
+        assert_eq!(read_all(&mut reader, 300).unwrap(), 40);
+        assert_eq!(reader.buf.len(), READ_CHUNK);
+        // Stays at the end of the stream.
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn empty_stream() {
+        let mut reader = FrameReader::new(Trickle(&[]));
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn cut_mid_frame() {
+        let bytes = wire(2, 10);
+        let frame_len = HEADER_LEN + 10;
+        // Within the header, then within the body, of the second frame.
+        for cut in [frame_len + 3, frame_len + HEADER_LEN + 4] {
+            let mut reader = FrameReader::new(Trickle(&bytes[..cut]));
+            assert!(reader.read_frame().unwrap().is_some());
+            let err = reader.read_frame().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "cut at {cut}");
+        }
+    }
+
+    #[test]
+    fn body_larger_than_chunk() {
+        let len = 3 * READ_CHUNK + 5;
+        let bytes = wire(2, len);
+        let mut reader = FrameReader::new(Trickle(&bytes));
+        assert_eq!(read_all(&mut reader, len).unwrap(), 2);
+        assert!(reader.buf.len() >= HEADER_LEN + len);
+    }
+
+    #[test]
+    fn invalid_frame() {
+        let mut bytes = wire(1, 10);
+        bytes[0] = 0xff;
+        let mut reader = FrameReader::new(Trickle(&bytes));
+        let err = reader.read_frame().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn write_data_partial_writes() {
+        let header = Header::data(StreamId::new(5), 11);
+        let payloads: [&[u8]; 4] = [b"", b"hello", b"", b" world"];
+        let mut writer = Dribble::default();
+        write_data(&mut writer, &header, &payloads).unwrap();
+
+        let mut expected = header.as_bytes().to_vec();
+        expected.extend_from_slice(b"hello world");
+        assert_eq!(writer.out, expected);
+
+        let mut reader = FrameReader::new(&writer.out[..]);
+        let frame = reader.read_frame().unwrap().unwrap();
+        assert_eq!(frame.body(), b"hello world");
+    }
+
+    #[test]
+    fn write_data_length_mismatch() {
+        let header = Header::data(StreamId::new(5), 4);
+        let mut writer = Dribble::default();
+        let err = write_data(&mut writer, &header, &[b"abc"]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(writer.out.is_empty());
+    }
+
+    #[test]
+    fn write_all_vectored_resumes() {
+        let mut writer = Dribble::default();
+        let mut slices = [
+            IoSlice::new(b""),
+            IoSlice::new(b"ab"),
+            IoSlice::new(b"cdefg"),
+            IoSlice::new(b""),
+            IoSlice::new(b"h"),
+        ];
+        write_all_vectored(&mut writer, &mut &mut slices[..]).unwrap();
+        assert_eq!(writer.out, b"abcdefgh");
+    }
+
+    #[test]
+    fn write_zero() {
+        let mut out = [0; 4];
+        let mut slices = [IoSlice::new(b"abc"), IoSlice::new(b"def")];
+        let mut bufs = &mut slices[..];
+        let err = write_all_vectored(&mut &mut out[..], &mut bufs).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(out, *b"abcd");
+        // What is left can be retried.
+        assert_eq!(bufs.len(), 1);
+        assert_eq!(&*bufs[0], b"ef");
+    }
+}
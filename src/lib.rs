@@ -0,0 +1,66 @@
+//! Zero-copy codec for the [yamux] stream multiplexing protocol.
+//!
+//! Frames are read in place from byte buffers with [`zerocopy`]: a [`Frame`]
+//! borrows its [`Header`] and body from the buffer it was parsed from. The `T`
+//! marker of `Frame<B, T>` names the kind of the frame ([`Data`],
+//! [`WindowUpdate`], [`Ping`] or [`GoAway`]), or is [`Untyped`] when the kind
+//! is only known at runtime.
+//!
+//! Parsing and encoding frames only needs `core`. The `alloc` feature adds
+//! `OwnedFrame` and the incremental `Decoder`, and the default `std` feature
+//! adds the `std::io` adapters of the `io` module and the sans-IO `Session`
+//! multiplexing streams over a connection.
+//!
+//! The `bytes` feature splits frames off `BytesMut` buffers as `BytesFrame`s,
+//! whose body shares the allocation of the buffer. The `tokio` and `futures`
+//! features build on it to provide codecs for `tokio_util` and
+//! `asynchronous_codec` in the `codec` module.
+//!
+//! [yamux]: https://github.com/hashicorp/yamux/blob/master/spec.md
+#![no_std]
+#![warn(missing_docs)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(any(feature = "tokio", feature = "futures"))]
+pub mod codec;
+#[cfg(feature = "alloc")]
+pub mod decoder;
+pub mod error;
+pub mod flags;
+pub mod frame;
+pub mod header;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "alloc")]
+pub mod owned;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(feature = "bytes")]
+pub mod split;
+pub mod stream;
+pub mod window;
+
+#[cfg(feature = "alloc")]
+pub use decoder::{Decode, Decoder};
+pub use error::FrameError;
+pub use flags::Flags;
+pub use frame::{frame_len, AnyFrame, Frame, Frames, ParseConfig, MAX_BODY_LEN};
+pub use header::{
+    Data, FrameKind, GoAway, GoAwayCode, Header, Len, Marker, Ping, StreamId, Tag, Untyped,
+    Version, VersionPolicy, WindowUpdate, HEADER_LEN,
+};
+#[cfg(feature = "std")]
+pub use io::{FrameReader, FrameWriter};
+#[cfg(feature = "alloc")]
+pub use owned::OwnedFrame;
+#[cfg(feature = "std")]
+pub use session::{Event, Session, SessionConfig, SessionError};
+#[cfg(feature = "bytes")]
+pub use split::BytesFrame;
+pub use stream::{InvalidTransition, Role, StreamIdAllocator, StreamIdError, StreamState};
+pub use window::{FlowControlError, RecvWindow, SendWindow, INITIAL_WINDOW};
+pub use zerocopy::{ByteSlice, ByteSliceMut};
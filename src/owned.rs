@@ -0,0 +1,168 @@
+//! Frames owning their header and body, to be queued or stored past the
+//! buffer they were parsed from.
+
+use alloc::vec::Vec;
+use zerocopy::{AsBytes, ByteSlice};
+
+use crate::error::FrameError;
+use crate::frame::{check_body_len, Frame, ParseConfig};
+use crate::header::{Data, FrameKind, Header, Marker, StreamId, Tag, Untyped, HEADER_LEN};
+
+/// Frame of the kind named by `T`, owning its header and a `Body` such as
+/// `Vec<u8>` or `Bytes`.
+///
+/// The body size always matches the one the header announces, so that
+/// [`OwnedFrame::as_frame`] can view it as a borrowed [`Frame`].
+#[derive(Clone, Debug)]
+pub struct OwnedFrame<T: Marker = Untyped, Body = Vec<u8>> {
+    pub(crate) header: Header<T>,
+    pub(crate) body: Body,
+}
+
+impl<T: Marker, Body: AsRef<[u8]>> OwnedFrame<T, Body> {
+    /// Frame made of `header` and `body`, whose size must match the one the
+    /// header announces.
+    pub fn new(header: Header<T>, body: Body) -> Result<Self, FrameError> {
+        let (tag, expected) = header.check(ParseConfig::default())?;
+        check_body_len(tag, expected, body.as_ref().len())?;
+        Ok(OwnedFrame { header, body })
+    }
+
+    /// Header of the frame.
+    pub fn header(&self) -> &Header<T> {
+        &self.header
+    }
+
+    /// Tag of the frame.
+    pub fn tag(&self) -> Tag {
+        self.header.tag().expect("tag checked on construction")
+    }
+
+    /// Body of the frame, only ever non-empty for Data frames.
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    /// Take the body out of the frame.
+    pub fn into_body(self) -> Body {
+        self.body
+    }
+
+    /// Number of bytes the frame takes on the wire, header included.
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + self.body.as_ref().len()
+    }
+
+    /// Borrowed view of the frame.
+    pub fn as_frame(&self) -> Frame<&[u8], T> {
+        Frame::from_parts(&self.header, self.body.as_ref(), self.tag())
+    }
+
+    /// Forget the kind of the frame, keeping the same header and body.
+    pub fn into_untyped(self) -> OwnedFrame<Untyped, Body> {
+        OwnedFrame {
+            header: self.header.into_untyped(),
+            body: self.body,
+        }
+    }
+}
+
+impl<Body: AsRef<[u8]>> OwnedFrame<Data, Body> {
+    /// Data frame carrying `body` on stream `id`.
+    pub fn data(id: StreamId, body: Body) -> Result<Self, FrameError> {
+        let len = u32::try_from(body.as_ref().len()).unwrap_or(u32::MAX);
+        Self::new(Header::data(id, len), body)
+    }
+}
+
+impl<Body: AsRef<[u8]>> OwnedFrame<Untyped, Body> {
+    /// Turn the frame into a frame of kind `T`, or hand it back if its tag is
+    /// not `T::TAG`.
+    pub fn cast<T: FrameKind>(self) -> Result<OwnedFrame<T, Body>, Self> {
+        if self.tag() != T::TAG {
+            return Err(self);
+        }
+        Ok(OwnedFrame {
+            header: self.header.retype(),
+            body: self.body,
+        })
+    }
+}
+
+impl<T: Marker, Body: AsRef<[u8]>> PartialEq for OwnedFrame<T, Body> {
+    fn eq(&self, other: &Self) -> bool {
+        self.header.as_bytes() == other.header.as_bytes()
+            && self.body.as_ref() == other.body.as_ref()
+    }
+}
+
+impl<T: Marker, Body: AsRef<[u8]>> Eq for OwnedFrame<T, Body> {}
+
+impl<B: ByteSlice, T: Marker> From<Frame<B, T>> for OwnedFrame<T> {
+    /// Copy the header and body of `frame`.
+    fn from(frame: Frame<B, T>) -> Self {
+        OwnedFrame {
+            header: *frame.header(),
+            body: frame.body().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    use crate::header::Ping;
+
+    #[test]
+    fn round_trip() {
+        let mut bytes = Header::data(StreamId::new(3), 5)
+            .with_syn()
+            .as_bytes()
+            .to_vec();
+        bytes.extend_from_slice(b"hello");
+        let frame = Frame::<_, Untyped>::parse(&bytes[..]).unwrap();
+        let owned = OwnedFrame::from(frame);
+        drop(bytes);
+
+        assert_eq!(owned.tag(), Tag::Data);
+        assert_eq!(owned.encoded_len(), HEADER_LEN + 5);
+        let view = owned.as_frame();
+        assert_eq!(view.header().stream_id(), StreamId::new(3));
+        assert_eq!(view.body(), b"hello");
+
+        let copy = owned.clone();
+        assert_eq!(copy, owned);
+        let data = copy.cast::<Data>().unwrap();
+        let header = Header::data(StreamId::new(3), 5).with_syn();
+        assert_eq!(data, OwnedFrame::new(header, b"hello".to_vec()).unwrap());
+        assert_ne!(
+            data,
+            OwnedFrame::data(StreamId::new(3), b"hello".to_vec()).unwrap()
+        );
+    }
+
+    #[test]
+    fn checked_on_construction() {
+        let header = Header::data(StreamId::new(1), 5);
+        assert_eq!(
+            OwnedFrame::new(header, vec![0; 4]).unwrap_err(),
+            FrameError::BodyLengthMismatch {
+                expected: 5,
+                actual: 4
+            }
+        );
+        assert_eq!(
+            OwnedFrame::new(Header::ping(1), vec![0; 4]).unwrap_err(),
+            FrameError::UnexpectedBody {
+                tag: Tag::Ping,
+                len: 4
+            }
+        );
+
+        let ping = OwnedFrame::new(Header::ping(1).into_untyped(), Vec::new()).unwrap();
+        let ping = ping.cast::<Data>().unwrap_err();
+        assert_eq!(ping.cast::<Ping>().unwrap().as_frame().opaque(), 1);
+    }
+}
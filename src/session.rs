@@ -0,0 +1,1096 @@
+//! Sans-IO yamux session, multiplexing streams over the frames exchanged with
+//! a peer.
+//!
+//! A [`Session`] never touches sockets or clocks. The driver feeds it the
+//! bytes read from the transport with [`Session::handle_input`] and wakes it
+//! up with [`Session::handle_timeout`], passing the current time to both,
+//! then drains the frames to send with [`Session::poll_transmit`] and what
+//! happened with [`Session::poll_event`].
+//!
+//! Streams are flow controlled: the application reports the data it is done
+//! with through [`Session::consume`], which grants the peer more credit, and
+//! optionally grows the receive window when it limits throughput (see
+//! [`SessionConfig::with_auto_tuning`]).
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Display};
+use core::mem;
+use std::time::{Duration, Instant};
+
+use crate::decoder::Decoder;
+use crate::error::FrameError;
+use crate::flags::Flags;
+use crate::frame::{Frame, ParseConfig, MAX_BODY_LEN};
+use crate::header::{GoAwayCode, Header, Marker, StreamId, Tag, Untyped};
+use crate::owned::OwnedFrame;
+use crate::stream::{InvalidTransition, Role, StreamIdAllocator, StreamIdError, StreamState};
+use crate::window::{FlowControlError, RecvWindow, SendWindow, INITIAL_WINDOW};
+
+/// Settings of a [`Session`].
+#[derive(Copy, Clone, Debug)]
+pub struct SessionConfig {
+    pub(crate) parse: ParseConfig,
+    pub(crate) keep_alive: Option<Duration>,
+    pub(crate) receive_window: u32,
+    pub(crate) window_update_threshold: u32,
+    pub(crate) auto_tuning: bool,
+    pub(crate) max_receive_window: u32,
+    pub(crate) receive_budget: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            parse: ParseConfig::strict(),
+            keep_alive: Some(Duration::from_secs(30)),
+            receive_window: INITIAL_WINDOW,
+            window_update_threshold: INITIAL_WINDOW / 2,
+            auto_tuning: false,
+            max_receive_window: 16 * 1024 * 1024,
+            receive_budget: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Rules applied to incoming frames, [`ParseConfig::strict`] by default.
+    pub fn with_parse_config(mut self, parse: ParseConfig) -> Self {
+        self.parse = parse;
+        self
+    }
+
+    /// Interval between the Pings sent to keep the connection alive, 30
+    /// seconds by default, or `None` to never send them.
+    ///
+    /// A Ping left unanswered for a whole interval terminates the session.
+    pub fn with_keep_alive(mut self, keep_alive: Option<Duration>) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Receive window of each stream, [`INITIAL_WINDOW`] by default and never
+    /// less.
+    pub fn with_receive_window(mut self, receive_window: u32) -> Self {
+        self.receive_window = receive_window.max(INITIAL_WINDOW);
+        self
+    }
+
+    /// Number of bytes the application must consume on a stream before the
+    /// peer is granted more credit, half the initial window by default and
+    /// never more than the receive window.
+    pub fn with_window_update_threshold(mut self, threshold: u32) -> Self {
+        self.window_update_threshold = threshold;
+        self
+    }
+
+    /// Whether to grow the receive window of streams whose data the
+    /// application drains at more than half the pace the window allows, one
+    /// window per round trip, disabled by default.
+    ///
+    /// A stream limited by its window drains exactly one window per round
+    /// trip, so the margin is what lets such streams grow at all.
+    ///
+    /// Round-trip times are measured with Pings, the first one being sent
+    /// when the session starts.
+    pub fn with_auto_tuning(mut self, auto_tuning: bool) -> Self {
+        self.auto_tuning = auto_tuning;
+        self
+    }
+
+    /// Size receive windows can grow to when auto-tuned, 16 MiB by default.
+    pub fn with_max_receive_window(mut self, max_receive_window: u32) -> Self {
+        self.max_receive_window = max_receive_window;
+        self
+    }
+
+    /// Total size of the receive windows of all streams beyond which
+    /// auto-tuning stops growing them, 64 MiB by default.
+    pub fn with_receive_budget(mut self, receive_budget: u64) -> Self {
+        self.receive_budget = receive_budget;
+        self
+    }
+}
+
+/// Something that happened on a session, returned by [`Session::poll_event`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The peer opened a stream.
+    StreamOpened(StreamId),
+    /// The peer sent data on a stream.
+    ///
+    /// The data is copied out of the input passed to
+    /// [`Session::handle_input`], since events outlive it. Applications that
+    /// cannot afford the copy should decode frames themselves with a
+    /// [`Decoder`].
+    Data {
+        /// Stream the data was sent on.
+        stream_id: StreamId,
+        /// Data sent.
+        data: Vec<u8>,
+    },
+    /// The peer closed its side of a stream, and will send no more data on it.
+    StreamClosed(StreamId),
+    /// The peer reset a stream.
+    StreamReset(StreamId),
+    /// The peer granted credit on a stream that had run out of it, so data
+    /// can be sent on it again.
+    Writable(StreamId),
+    /// The peer answered the last Ping sent.
+    Pong {
+        /// Round-trip time of the Ping.
+        rtt: Duration,
+    },
+    /// The peer is going away, and will accept no new streams.
+    GoAway(u32),
+}
+
+/// Error raised by a [`Session`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SessionError {
+    /// The peer sent an invalid frame.
+    Frame(FrameError),
+    /// The peer opened a stream that is already open.
+    DuplicateStream(u32),
+    /// A stream ID could not be allocated, or the peer opened a stream with
+    /// an ID it may not use.
+    StreamId(StreamIdError),
+    /// The peer sent a frame its stream does not allow in its current state.
+    Transition {
+        /// Stream the frame was sent on.
+        stream_id: u32,
+        /// Rejected transition.
+        error: InvalidTransition,
+    },
+    /// The peer violated flow control on a stream.
+    FlowControl {
+        /// Stream the violation happened on.
+        stream_id: u32,
+        /// Violation.
+        error: FlowControlError,
+    },
+    /// The stream is not open.
+    UnknownStream(u32),
+    /// The stream was closed for writing.
+    StreamClosed(u32),
+    /// A GoAway was sent or received, so no new stream can be opened.
+    GoingAway,
+    /// The peer did not answer a Ping within the keep-alive interval.
+    Timeout,
+    /// The session was terminated after an earlier error.
+    Closed,
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Frame(err) => write!(f, "invalid frame: {err}"),
+            SessionError::DuplicateStream(id) => write!(f, "stream {id} opened twice"),
+            SessionError::StreamId(err) => write!(f, "invalid stream ID: {err}"),
+            SessionError::Transition { stream_id, error } => {
+                write!(f, "on stream {stream_id}: {error}")
+            }
+            SessionError::FlowControl { stream_id, error } => {
+                write!(f, "on stream {stream_id}: {error}")
+            }
+            SessionError::UnknownStream(id) => write!(f, "stream {id} is not open"),
+            SessionError::StreamClosed(id) => write!(f, "stream {id} is closed for writing"),
+            SessionError::GoingAway => f.write_str("session is going away"),
+            SessionError::Timeout => f.write_str("keep-alive Ping timed out"),
+            SessionError::Closed => f.write_str("session terminated"),
+        }
+    }
+}
+
+impl Error for SessionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SessionError::Frame(err) => Some(err),
+            SessionError::StreamId(err) => Some(err),
+            SessionError::Transition { error, .. } => Some(error),
+            SessionError::FlowControl { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<FrameError> for SessionError {
+    fn from(err: FrameError) -> Self {
+        SessionError::Frame(err)
+    }
+}
+
+impl From<StreamIdError> for SessionError {
+    fn from(err: StreamIdError) -> Self {
+        SessionError::StreamId(err)
+    }
+}
+
+/// Stream tracked by a session.
+#[derive(Debug)]
+struct Stream {
+    state: StreamState,
+    send: SendWindow,
+    recv: RecvWindow,
+    // Start of the current auto-tuning epoch, and bytes consumed since.
+    epoch_start: Instant,
+    epoch_consumed: u64,
+}
+
+impl Stream {
+    fn new(receive_window: u32, now: Instant) -> Self {
+        Stream {
+            state: StreamState::default(),
+            send: SendWindow::default(),
+            recv: RecvWindow::new(receive_window),
+            epoch_start: now,
+            epoch_consumed: 0,
+        }
+    }
+
+    /// Double the receive window if the application drained the data of the
+    /// epoch ending at `now` faster than one window per two round trips, half
+    /// the pace at which the window limits throughput.
+    ///
+    /// Growth is capped by `config` and the bytes left in the session budget,
+    /// given `reserved` bytes already taken by the windows of all streams.
+    fn tune(&mut self, now: Instant, rtt: Duration, config: &SessionConfig, reserved: &mut u64) {
+        let elapsed = now.saturating_duration_since(self.epoch_start);
+        let consumed = u128::from(self.epoch_consumed);
+        self.epoch_start = now;
+        self.epoch_consumed = 0;
+        let max = self.recv.max();
+        if elapsed.as_nanos() * u128::from(max) >= 2 * rtt.as_nanos() * consumed {
+            return;
+        }
+        let budget = config.receive_budget.saturating_sub(*reserved);
+        let target = max.saturating_mul(2).min(config.max_receive_window);
+        let extra = u64::from(target.saturating_sub(max)).min(budget) as u32;
+        self.recv.grow_to(max + extra);
+        *reserved += u64::from(extra);
+    }
+}
+
+/// Yamux session, see the module documentation.
+#[derive(Debug)]
+pub struct Session {
+    config: SessionConfig,
+    decoder: Decoder,
+    streams: BTreeMap<u32, Stream>,
+    ids: StreamIdAllocator,
+    transmit: VecDeque<OwnedFrame>,
+    events: VecDeque<Event>,
+    now: Instant,
+    next_keep_alive: Option<Instant>,
+    // Opaque value and send time of the Ping awaiting an answer.
+    ping: Option<(u32, Instant)>,
+    next_opaque: u32,
+    // Smoothed round-trip time, once measured.
+    rtt: Option<Duration>,
+    // Sum of the receive windows of all streams.
+    reserved: u64,
+    local_go_away: bool,
+    remote_go_away: bool,
+    closed: bool,
+}
+
+impl Session {
+    /// Session playing `role`, started at `now`.
+    pub fn new(role: Role, config: SessionConfig, now: Instant) -> Self {
+        let mut session = Session {
+            config,
+            decoder: Decoder::with_config(config.parse),
+            streams: BTreeMap::new(),
+            ids: StreamIdAllocator::new(role),
+            transmit: VecDeque::new(),
+            events: VecDeque::new(),
+            now,
+            next_keep_alive: config.keep_alive.map(|interval| now + interval),
+            ping: None,
+            next_opaque: 0,
+            rtt: None,
+            reserved: 0,
+            local_go_away: false,
+            remote_go_away: false,
+            closed: false,
+        };
+        if config.auto_tuning {
+            session.send_ping(now);
+        }
+        session
+    }
+
+    /// Side of the connection the session plays.
+    pub fn role(&self) -> Role {
+        self.ids.role()
+    }
+
+    /// Smoothed round-trip time to the peer, once a Ping was answered.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Whether the session was terminated after an error.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Process bytes read from the transport at `now`.
+    ///
+    /// If the peer violated the protocol, a GoAway is queued for transmission
+    /// and the session is terminated.
+    pub fn handle_input(&mut self, now: Instant, input: &[u8]) -> Result<(), SessionError> {
+        if self.closed {
+            return Err(SessionError::Closed);
+        }
+        self.now = now;
+        let mut decoder = mem::take(&mut self.decoder);
+        let result = self.decode(&mut decoder, input);
+        self.decoder = decoder;
+        result.map_err(|err| self.fail(err))
+    }
+
+    /// Tell the session the current time, sending the keep-alive Ping if it
+    /// is due.
+    ///
+    /// If the previous Ping is still unanswered a keep-alive interval after it
+    /// was sent, the session is terminated with a GoAway.
+    pub fn handle_timeout(&mut self, now: Instant) -> Result<(), SessionError> {
+        if self.closed {
+            return Err(SessionError::Closed);
+        }
+        self.now = now;
+        let (Some(deadline), Some(interval)) = (self.next_keep_alive, self.config.keep_alive)
+        else {
+            return Ok(());
+        };
+        if now < deadline {
+            return Ok(());
+        }
+        match self.ping {
+            Some((_, sent)) if now.saturating_duration_since(sent) >= interval => {
+                return Err(self.fail(SessionError::Timeout));
+            }
+            // Give the outstanding Ping a whole interval to be answered.
+            Some((_, sent)) => self.next_keep_alive = Some(sent + interval),
+            None => {
+                self.send_ping(now);
+                self.next_keep_alive = Some(now + interval);
+            }
+        }
+        Ok(())
+    }
+
+    /// Time at which [`Session::handle_timeout`] should be called next.
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        if self.closed {
+            return None;
+        }
+        self.next_keep_alive
+    }
+
+    /// Next frame to write to the transport.
+    pub fn poll_transmit(&mut self) -> Option<OwnedFrame> {
+        self.transmit.pop_front()
+    }
+
+    /// Next event to report to the application.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Open a new stream.
+    ///
+    /// Once stream IDs are exhausted, a GoAway is sent so that the connection
+    /// can be replaced.
+    pub fn open_stream(&mut self) -> Result<StreamId, SessionError> {
+        if self.closed {
+            return Err(SessionError::Closed);
+        }
+        if self.local_go_away || self.remote_go_away {
+            return Err(SessionError::GoingAway);
+        }
+        let id = match self.ids.allocate() {
+            Ok(id) => id,
+            Err(err) => {
+                self.send_go_away(GoAwayCode::Normal);
+                return Err(err.into());
+            }
+        };
+        let mut stream = Stream::new(self.config.receive_window, self.now);
+        let flags = stream.state.send_flags().expect("new streams are writable");
+        let delta = stream.recv.grant();
+        self.insert_stream(id, stream);
+        self.queue(Header::window_update(id, delta).with_flags(flags));
+        Ok(id)
+    }
+
+    /// Send the front of `data` on stream `id`, returning the number of bytes
+    /// sent.
+    ///
+    /// Fewer bytes than requested are sent once the send window of the
+    /// stream runs out, until [`Event::Writable`] reports the peer granted
+    /// more credit.
+    ///
+    /// The bytes sent are copied into the frame queued for transmission, so
+    /// that `data` can be reused right away, then copied again when the driver
+    /// writes the frame to the transport.
+    pub fn send_data(&mut self, id: StreamId, data: &[u8]) -> Result<usize, SessionError> {
+        let stream = self.stream_mut(id)?;
+        if !stream.state.is_writable() {
+            return Err(SessionError::StreamClosed(id.val()));
+        }
+        let len = stream.send.consume(data.len().min(MAX_BODY_LEN as usize));
+        if len == 0 {
+            return Ok(0);
+        }
+        let flags = stream.state.send_flags().expect("stream checked writable");
+        let header = Header::data(id, len as u32)
+            .with_flags(flags)
+            .into_untyped();
+        let frame = OwnedFrame::new(header, data[..len].to_vec()).expect("body fits the header");
+        self.transmit.push_back(frame);
+        Ok(len)
+    }
+
+    /// Tell the session the application consumed `len` bytes of the data
+    /// received on stream `id` by `now`, granting the peer more credit once
+    /// enough bytes were consumed.
+    ///
+    /// With auto-tuning, the window of the stream may grow at the same time.
+    pub fn consume(&mut self, now: Instant, id: StreamId, len: usize) {
+        if self.closed {
+            return;
+        }
+        self.now = now;
+        let Some(stream) = self.streams.get_mut(&id.val()) else {
+            return;
+        };
+        // The peer is done sending, no need for more credit.
+        if !stream.state.is_readable() {
+            return;
+        }
+        let len = u32::try_from(len).unwrap_or(u32::MAX);
+        stream.epoch_consumed += u64::from(len);
+        let threshold = self.config.window_update_threshold;
+        let Some(mut delta) = stream.recv.consume(len, threshold) else {
+            return;
+        };
+        if let (true, Some(rtt)) = (self.config.auto_tuning, self.rtt) {
+            stream.tune(self.now, rtt, &self.config, &mut self.reserved);
+            delta += stream.recv.grant();
+        }
+        let flags = stream.state.send_flags().unwrap_or_default();
+        self.queue(Header::window_update(id, delta).with_flags(flags));
+    }
+
+    /// Close the local side of stream `id`, sending no more data on it.
+    pub fn close_stream(&mut self, id: StreamId) -> Result<(), SessionError> {
+        let stream = self.stream_mut(id)?;
+        let flags = stream
+            .state
+            .send_fin()
+            .ok_or(SessionError::StreamClosed(id.val()))?;
+        if stream.state.is_closed() {
+            self.remove_stream(id);
+        }
+        self.queue(Header::window_update(id, 0).with_flags(flags));
+        Ok(())
+    }
+
+    /// Abruptly terminate stream `id` in both directions.
+    pub fn reset_stream(&mut self, id: StreamId) -> Result<(), SessionError> {
+        let flags = self.stream_mut(id)?.state.send_rst();
+        self.remove_stream(id);
+        self.queue(Header::window_update(id, 0).with_flags(flags));
+        Ok(())
+    }
+
+    /// Send a Ping at `now`, answered by an [`Event::Pong`].
+    ///
+    /// A Ping still awaiting an answer is forgotten.
+    pub fn ping(&mut self, now: Instant) -> Result<(), SessionError> {
+        if self.closed {
+            return Err(SessionError::Closed);
+        }
+        self.send_ping(now);
+        Ok(())
+    }
+
+    /// Tell the peer the session is going away with `code`, refusing new
+    /// streams from then on.
+    pub fn go_away(&mut self, code: GoAwayCode) -> Result<(), SessionError> {
+        if self.closed {
+            return Err(SessionError::Closed);
+        }
+        self.send_go_away(code);
+        Ok(())
+    }
+
+    fn decode(&mut self, decoder: &mut Decoder, input: &[u8]) -> Result<(), SessionError> {
+        let mut decode = decoder.decode(input);
+        while let Some(frame) = decode.next_frame() {
+            self.on_frame(frame?)?;
+        }
+        Ok(())
+    }
+
+    fn on_frame(&mut self, frame: Frame<&[u8], Untyped>) -> Result<(), SessionError> {
+        let header = frame.header();
+        let flags = header.flags();
+        match frame.tag() {
+            tag @ (Tag::Data | Tag::WindowUpdate) => self.on_stream_frame(
+                header.stream_id(),
+                tag,
+                flags,
+                frame.length().val(),
+                frame.body(),
+            ),
+            Tag::Ping => {
+                self.on_ping(flags, frame.length().val());
+                Ok(())
+            }
+            Tag::GoAway => {
+                self.remote_go_away = true;
+                self.events.push_back(Event::GoAway(frame.length().val()));
+                Ok(())
+            }
+        }
+    }
+
+    fn on_stream_frame(
+        &mut self,
+        id: StreamId,
+        tag: Tag,
+        mut flags: Flags,
+        length: u32,
+        body: &[u8],
+    ) -> Result<(), SessionError> {
+        if flags.contains(Flags::SYN) {
+            if self.streams.contains_key(&id.val()) {
+                return Err(SessionError::DuplicateStream(id.val()));
+            }
+            self.ids.accept(id)?;
+            if self.local_go_away {
+                self.queue(Header::window_update(id, 0).with_rst());
+                return Ok(());
+            }
+            // Acknowledge the stream right away, even if the frame also
+            // closes it.
+            let mut stream = Stream::new(self.config.receive_window, self.now);
+            stream
+                .state
+                .on_recv(tag, Flags::SYN)
+                .expect("new streams accept SYN");
+            let ack = stream.state.send_flags().expect("new streams are writable");
+            let delta = stream.recv.grant();
+            self.insert_stream(id, stream);
+            self.events.push_back(Event::StreamOpened(id));
+            self.queue(Header::window_update(id, delta).with_flags(ack));
+            flags.remove(Flags::SYN);
+        }
+        // Frames may still be in flight on a stream we already reset.
+        let Some(stream) = self.streams.get_mut(&id.val()) else {
+            return Ok(());
+        };
+        stream
+            .state
+            .on_recv(tag, flags)
+            .map_err(|error| SessionError::Transition {
+                stream_id: id.val(),
+                error,
+            })?;
+        let flow_control = |error| SessionError::FlowControl {
+            stream_id: id.val(),
+            error,
+        };
+        let mut writable = false;
+        match tag {
+            Tag::Data => stream.recv.on_data(length).map_err(flow_control)?,
+            _ => {
+                writable = stream.send.credit() == 0 && length > 0 && stream.state.is_writable();
+                stream.send.grow(length).map_err(flow_control)?;
+            }
+        }
+        if stream.state.is_closed() {
+            self.remove_stream(id);
+        }
+        if !body.is_empty() {
+            self.events.push_back(Event::Data {
+                stream_id: id,
+                data: body.to_vec(),
+            });
+        }
+        if writable {
+            self.events.push_back(Event::Writable(id));
+        }
+        if flags.contains(Flags::FIN) {
+            self.events.push_back(Event::StreamClosed(id));
+        }
+        if flags.contains(Flags::RST) {
+            self.events.push_back(Event::StreamReset(id));
+        }
+        Ok(())
+    }
+
+    fn on_ping(&mut self, flags: Flags, opaque: u32) {
+        if flags.contains(Flags::SYN) {
+            self.queue(Header::ping(opaque).with_ack());
+        } else if flags.contains(Flags::ACK) {
+            if let Some((expected, sent)) = self.ping {
+                if opaque == expected {
+                    self.ping = None;
+                    let rtt = self.now.saturating_duration_since(sent);
+                    self.rtt = Some(match self.rtt {
+                        Some(smoothed) => (smoothed * 7 + rtt) / 8,
+                        None => rtt,
+                    });
+                    self.events.push_back(Event::Pong { rtt });
+                }
+            }
+        }
+    }
+
+    /// Open stream `id`, for the application to act on.
+    fn stream_mut(&mut self, id: StreamId) -> Result<&mut Stream, SessionError> {
+        if self.closed {
+            return Err(SessionError::Closed);
+        }
+        self.streams
+            .get_mut(&id.val())
+            .ok_or(SessionError::UnknownStream(id.val()))
+    }
+
+    fn insert_stream(&mut self, id: StreamId, stream: Stream) {
+        self.reserved += u64::from(stream.recv.max());
+        self.streams.insert(id.val(), stream);
+    }
+
+    fn remove_stream(&mut self, id: StreamId) {
+        if let Some(stream) = self.streams.remove(&id.val()) {
+            self.reserved -= u64::from(stream.recv.max());
+        }
+    }
+
+    /// Queue a frame without body for transmission.
+    fn queue<T: Marker>(&mut self, header: Header<T>) {
+        let frame = OwnedFrame::new(header.into_untyped(), Vec::new()).expect("frame has no body");
+        self.transmit.push_back(frame);
+    }
+
+    /// Queue a Ping sent at `now`, forgetting any Ping awaiting an answer.
+    fn send_ping(&mut self, now: Instant) {
+        self.now = now;
+        let opaque = self.next_opaque;
+        self.next_opaque = self.next_opaque.wrapping_add(1);
+        self.ping = Some((opaque, now));
+        self.queue(Header::ping(opaque).with_syn());
+    }
+
+    /// Queue a GoAway with `code`, unless one was already sent.
+    fn send_go_away(&mut self, code: GoAwayCode) {
+        if !self.local_go_away {
+            self.local_go_away = true;
+            self.queue(Header::go_away(code.into()));
+        }
+    }
+
+    /// Terminate the session after `err`, telling the peer with a GoAway.
+    fn fail(&mut self, err: SessionError) -> SessionError {
+        self.send_go_away(match err {
+            SessionError::Timeout => GoAwayCode::InternalError,
+            _ => GoAwayCode::ProtocolError,
+        });
+        self.closed = true;
+        self.streams.clear();
+        self.reserved = 0;
+        err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use core::iter;
+    use zerocopy::AsBytes;
+
+    use crate::header::HEADER_LEN;
+
+    const RTT: Duration = Duration::from_millis(10);
+
+    fn pair(config: SessionConfig, now: Instant) -> (Session, Session) {
+        (
+            Session::new(Role::Client, config, now),
+            Session::new(Role::Server, config, now),
+        )
+    }
+
+    /// Write the frames queued by `from` and feed them to `to` at `now`.
+    fn transfer(from: &mut Session, to: &mut Session, now: Instant) -> Result<(), SessionError> {
+        let mut bytes = Vec::new();
+        while let Some(frame) = from.poll_transmit() {
+            bytes.extend_from_slice(frame.header().as_bytes());
+            bytes.extend_from_slice(frame.body());
+        }
+        to.handle_input(now, &bytes)
+    }
+
+    fn events(session: &mut Session) -> Vec<Event> {
+        iter::from_fn(|| session.poll_event()).collect()
+    }
+
+    fn transmitted(session: &mut Session) -> Vec<OwnedFrame> {
+        iter::from_fn(|| session.poll_transmit()).collect()
+    }
+
+    /// Code of the GoAway queued by `session`, if any.
+    fn go_away_code(session: &mut Session) -> Option<u32> {
+        transmitted(session)
+            .into_iter()
+            .find(|frame| frame.tag() == Tag::GoAway)
+            .map(|frame| frame.as_frame().length().val())
+    }
+
+    /// Open a stream from `client` to `server`, acknowledged by the server.
+    fn open(client: &mut Session, server: &mut Session, now: Instant) -> StreamId {
+        let id = client.open_stream().unwrap();
+        transfer(client, server, now).unwrap();
+        transfer(server, client, now).unwrap();
+        events(server);
+        id
+    }
+
+    /// Send `len` bytes from `client` on stream `id`, returning the number of
+    /// bytes the server received.
+    fn send(
+        client: &mut Session,
+        server: &mut Session,
+        id: StreamId,
+        len: usize,
+        now: Instant,
+    ) -> usize {
+        let data = vec![0; len];
+        let mut sent = 0;
+        while sent < len {
+            match client.send_data(id, &data[sent..]).unwrap() {
+                0 => break,
+                n => sent += n,
+            }
+        }
+        transfer(client, server, now).unwrap();
+        events(server)
+            .into_iter()
+            .map(|event| match event {
+                Event::Data { data, .. } => data.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn handshake() {
+        let now = Instant::now();
+        let (mut client, mut server) = pair(SessionConfig::default(), now);
+        let id = client.open_stream().unwrap();
+        assert_eq!(id.val(), 1);
+        let syn = transmitted(&mut client);
+        assert_eq!(syn[0].header().flags(), Flags::SYN);
+        client.transmit.extend(syn);
+
+        transfer(&mut client, &mut server, now).unwrap();
+        assert_eq!(events(&mut server), [Event::StreamOpened(id)]);
+        let ack = transmitted(&mut server);
+        assert_eq!(ack[0].header().flags(), Flags::ACK);
+        server.transmit.extend(ack);
+
+        transfer(&mut server, &mut client, now).unwrap();
+        assert_eq!(events(&mut client), []);
+        assert_eq!(client.streams[&1].state, StreamState::Established);
+        assert_eq!(server.streams[&1].state, StreamState::Established);
+    }
+
+    #[test]
+    fn data() {
+        let now = Instant::now();
+        let (mut client, mut server) = pair(SessionConfig::default(), now);
+        let id = client.open_stream().unwrap();
+        assert_eq!(client.send_data(id, b"hello"), Ok(5));
+        transfer(&mut client, &mut server, now).unwrap();
+        assert_eq!(
+            events(&mut server),
+            [
+                Event::StreamOpened(id),
+                Event::Data {
+                    stream_id: id,
+                    data: b"hello".to_vec(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn window_exhaustion() {
+        let now = Instant::now();
+        let (mut client, mut server) = pair(SessionConfig::default(), now);
+        let id = open(&mut client, &mut server, now);
+
+        let window = INITIAL_WINDOW as usize;
+        assert_eq!(send(&mut client, &mut server, id, window + 1, now), window);
+        assert_eq!(client.send_data(id, b"more").unwrap(), 0);
+
+        server.consume(now, id, window);
+        transfer(&mut server, &mut client, now).unwrap();
+        assert_eq!(events(&mut client), [Event::Writable(id)]);
+        assert_eq!(client.send_data(id, b"more").unwrap(), 4);
+    }
+
+    #[test]
+    fn threshold_above_window() {
+        let now = Instant::now();
+        let config = SessionConfig::default().with_window_update_threshold(u32::MAX);
+        let (mut client, mut server) = pair(config, now);
+        let id = open(&mut client, &mut server, now);
+
+        let window = INITIAL_WINDOW as usize;
+        assert_eq!(send(&mut client, &mut server, id, window, now), window);
+        server.consume(now, id, window);
+        transfer(&mut server, &mut client, now).unwrap();
+        assert_eq!(events(&mut client), [Event::Writable(id)]);
+    }
+
+    #[test]
+    fn close() {
+        let now = Instant::now();
+        let (mut client, mut server) = pair(SessionConfig::default(), now);
+        let id = open(&mut client, &mut server, now);
+
+        client.close_stream(id).unwrap();
+        transfer(&mut client, &mut server, now).unwrap();
+        assert_eq!(events(&mut server), [Event::StreamClosed(id)]);
+        assert_eq!(
+            client.send_data(id, b"data"),
+            Err(SessionError::StreamClosed(1))
+        );
+        assert_eq!(server.send_data(id, b"data"), Ok(4));
+
+        server.close_stream(id).unwrap();
+        transfer(&mut server, &mut client, now).unwrap();
+        assert_eq!(
+            events(&mut client),
+            [
+                Event::Data {
+                    stream_id: id,
+                    data: b"data".to_vec(),
+                },
+                Event::StreamClosed(id),
+            ]
+        );
+        assert!(client.streams.is_empty());
+        assert!(server.streams.is_empty());
+        assert_eq!(client.reserved, 0);
+    }
+
+    #[test]
+    fn reset() {
+        let now = Instant::now();
+        let (mut client, mut server) = pair(SessionConfig::default(), now);
+        let id = open(&mut client, &mut server, now);
+
+        server.reset_stream(id).unwrap();
+        transfer(&mut server, &mut client, now).unwrap();
+        assert_eq!(events(&mut client), [Event::StreamReset(id)]);
+        assert_eq!(
+            client.send_data(id, b"data"),
+            Err(SessionError::UnknownStream(1))
+        );
+        assert!(client.streams.is_empty());
+        assert!(server.streams.is_empty());
+    }
+
+    #[test]
+    fn wrong_parity() {
+        let now = Instant::now();
+        let mut server = Session::new(Role::Server, SessionConfig::default(), now);
+        let syn = Header::window_update(StreamId::new(2), 0).with_syn();
+        assert_eq!(
+            server.handle_input(now, syn.as_bytes()),
+            Err(SessionError::StreamId(StreamIdError::WrongParity(2)))
+        );
+        assert!(server.is_closed());
+        assert_eq!(
+            go_away_code(&mut server),
+            Some(GoAwayCode::ProtocolError.into())
+        );
+    }
+
+    #[test]
+    fn not_increasing() {
+        let now = Instant::now();
+        let mut server = Session::new(Role::Server, SessionConfig::default(), now);
+        let syn = |id| Header::window_update(StreamId::new(id), 0).with_syn();
+        server.handle_input(now, syn(3).as_bytes()).unwrap();
+        assert_eq!(
+            server.handle_input(now, syn(1).as_bytes()),
+            Err(SessionError::StreamId(StreamIdError::NotIncreasing {
+                id: 1,
+                last: 3
+            }))
+        );
+        assert_eq!(
+            go_away_code(&mut server),
+            Some(GoAwayCode::ProtocolError.into())
+        );
+        assert_eq!(server.open_stream(), Err(SessionError::Closed));
+    }
+
+    #[test]
+    fn ids_exhausted() {
+        let now = Instant::now();
+        let mut client = Session::new(Role::Client, SessionConfig::default(), now);
+        client.ids = StreamIdAllocator::starting_at(Role::Client, u32::MAX);
+        assert_eq!(client.open_stream().unwrap().val(), u32::MAX);
+        assert_eq!(
+            client.open_stream(),
+            Err(SessionError::StreamId(StreamIdError::Exhausted))
+        );
+        assert_eq!(go_away_code(&mut client), Some(GoAwayCode::Normal.into()));
+        assert_eq!(client.open_stream(), Err(SessionError::GoingAway));
+    }
+
+    #[test]
+    fn flow_control_violation() {
+        let now = Instant::now();
+        let (mut client, mut server) = pair(SessionConfig::default(), now);
+        let id = open(&mut client, &mut server, now);
+
+        let mut input = Header::data(id, INITIAL_WINDOW + 1).as_bytes().to_vec();
+        input.resize(HEADER_LEN + INITIAL_WINDOW as usize + 1, 0);
+        assert_eq!(
+            server.handle_input(now, &input),
+            Err(SessionError::FlowControl {
+                stream_id: 1,
+                error: FlowControlError::WindowExceeded {
+                    len: INITIAL_WINDOW + 1,
+                    credit: INITIAL_WINDOW,
+                },
+            })
+        );
+        assert_eq!(
+            go_away_code(&mut server),
+            Some(GoAwayCode::ProtocolError.into())
+        );
+        assert!(server.streams.is_empty());
+    }
+
+    #[test]
+    fn ping_timeout() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(1);
+        let config = SessionConfig::default().with_keep_alive(Some(interval));
+        let (mut client, mut server) = pair(config, start);
+
+        assert_eq!(client.poll_timeout(), Some(start + interval));
+        client.handle_timeout(start + interval).unwrap();
+        transfer(&mut client, &mut server, start + interval).unwrap();
+        transfer(&mut server, &mut client, start + interval + RTT).unwrap();
+        assert_eq!(events(&mut client), [Event::Pong { rtt: RTT }]);
+
+        let now = start + 2 * interval;
+        client.handle_timeout(now).unwrap();
+        assert_eq!(
+            client.handle_timeout(now + interval),
+            Err(SessionError::Timeout)
+        );
+        assert!(client.is_closed());
+        assert_eq!(
+            go_away_code(&mut client),
+            Some(GoAwayCode::InternalError.into())
+        );
+        assert_eq!(client.ping(now), Err(SessionError::Closed));
+        assert_eq!(
+            client.go_away(GoAwayCode::Normal),
+            Err(SessionError::Closed)
+        );
+        assert_eq!(client.poll_transmit(), None);
+    }
+
+    /// Measure the round-trip time from `server` to `client`, returning the
+    /// time the Pong is received.
+    fn measure_rtt(client: &mut Session, server: &mut Session, now: Instant) -> Instant {
+        transfer(server, client, now + RTT / 2).unwrap();
+        transfer(client, server, now + RTT).unwrap();
+        assert_eq!(server.rtt(), Some(RTT));
+        events(server);
+        now + RTT
+    }
+
+    /// Stream data from `client` to `server` for `rounds` round trips, the
+    /// server consuming it as soon as it arrives.
+    fn stream(
+        client: &mut Session,
+        server: &mut Session,
+        id: StreamId,
+        mut now: Instant,
+        rounds: usize,
+    ) {
+        for _ in 0..rounds {
+            let len = send(client, server, id, MAX_BODY_LEN as usize, now + RTT / 2);
+            server.consume(now + RTT / 2, id, len);
+            now += RTT;
+            transfer(server, client, now).unwrap();
+            events(client);
+        }
+    }
+
+    #[test]
+    fn auto_tuning() {
+        let now = Instant::now();
+        let max = 4 * INITIAL_WINDOW;
+        let config = SessionConfig::default()
+            .with_auto_tuning(true)
+            .with_max_receive_window(max);
+        let (mut client, mut server) = pair(config, now);
+        let now = measure_rtt(&mut client, &mut server, now);
+        let id = open(&mut client, &mut server, now);
+
+        stream(&mut client, &mut server, id, now, 8);
+        assert_eq!(server.streams[&1].recv.max(), max);
+        assert_eq!(server.reserved, u64::from(max));
+        assert_eq!(client.streams[&1].send.credit(), max);
+    }
+
+    #[test]
+    fn auto_tuning_budget() {
+        let now = Instant::now();
+        let budget = 3 * u64::from(INITIAL_WINDOW);
+        let config = SessionConfig::default()
+            .with_auto_tuning(true)
+            .with_receive_budget(budget);
+        let (mut client, mut server) = pair(config, now);
+        let now = measure_rtt(&mut client, &mut server, now);
+        let first = open(&mut client, &mut server, now);
+        let second = open(&mut client, &mut server, now);
+
+        stream(&mut client, &mut server, first, now, 8);
+        stream(&mut client, &mut server, second, now, 8);
+        assert_eq!(server.streams[&1].recv.max(), 2 * INITIAL_WINDOW);
+        assert_eq!(server.streams[&3].recv.max(), INITIAL_WINDOW);
+        assert_eq!(server.reserved, budget);
+    }
+
+    #[test]
+    fn slow_consumer() {
+        let now = Instant::now();
+        let config = SessionConfig::default().with_auto_tuning(true);
+        let (mut client, mut server) = pair(config, now);
+        let now = measure_rtt(&mut client, &mut server, now);
+        let id = open(&mut client, &mut server, now);
+
+        // Draining one window takes longer than a round trip.
+        let len = send(&mut client, &mut server, id, MAX_BODY_LEN as usize, now);
+        server.consume(now + 3 * RTT, id, len);
+        assert_eq!(server.streams[&1].recv.max(), INITIAL_WINDOW);
+    }
+}
@@ -0,0 +1,148 @@
+//! Frames split off [`BytesMut`] and [`Bytes`] buffers without copying.
+//!
+//! `zerocopy` does not let `Bytes` back a [`Frame`] directly, so the header
+//! is parsed in place and copied out, while the body is split off the buffer
+//! as a reference-counted [`Bytes`] sharing its allocation.
+
+use bytes::{Buf, Bytes, BytesMut};
+use zerocopy::ByteSlice;
+
+use crate::error::FrameError;
+use crate::frame::{Frame, ParseConfig};
+use crate::header::{Header, Marker, Untyped, HEADER_LEN};
+use crate::owned::OwnedFrame;
+
+/// Frame owning its header and a reference-counted body.
+pub type BytesFrame = OwnedFrame<Untyped, Bytes>;
+
+impl<T: Marker> OwnedFrame<T, Bytes> {
+    /// Split the frame at the front of `buf` off it, or return `None` and
+    /// reserve room for the missing bytes if the frame is incomplete.
+    pub fn split_from(buf: &mut BytesMut) -> Result<Option<Self>, FrameError> {
+        Self::split_from_with(buf, ParseConfig::default())
+    }
+
+    /// Split the frame at the front of `buf` off it following `config`, see
+    /// [`OwnedFrame::split_from`].
+    pub fn split_from_with(
+        buf: &mut BytesMut,
+        config: ParseConfig,
+    ) -> Result<Option<Self>, FrameError> {
+        let (header, body_len) = match parse_header::<T>(buf, config) {
+            Err(FrameError::Incomplete { needed }) => {
+                buf.reserve(needed);
+                return Ok(None);
+            }
+            parsed => parsed?,
+        };
+        buf.advance(HEADER_LEN);
+        let body = buf.split_to(body_len).freeze();
+        Ok(Some(OwnedFrame { header, body }))
+    }
+
+    /// Split the frame at the front of `buf` off it, or return `None` if the
+    /// frame is incomplete.
+    pub fn split_from_bytes(buf: &mut Bytes) -> Result<Option<Self>, FrameError> {
+        Self::split_from_bytes_with(buf, ParseConfig::default())
+    }
+
+    /// Split the frame at the front of `buf` off it following `config`, see
+    /// [`OwnedFrame::split_from_bytes`].
+    pub fn split_from_bytes_with(
+        buf: &mut Bytes,
+        config: ParseConfig,
+    ) -> Result<Option<Self>, FrameError> {
+        let (header, body_len) = match parse_header::<T>(buf, config) {
+            Err(FrameError::Incomplete { .. }) => return Ok(None),
+            parsed => parsed?,
+        };
+        buf.advance(HEADER_LEN);
+        let body = buf.split_to(body_len);
+        Ok(Some(OwnedFrame { header, body }))
+    }
+}
+
+impl<B: ByteSlice, T: Marker> From<Frame<B, T>> for OwnedFrame<T, Bytes> {
+    /// Copy the header and body of `frame`.
+    fn from(frame: Frame<B, T>) -> Self {
+        OwnedFrame {
+            header: *frame.header(),
+            body: Bytes::copy_from_slice(frame.body()),
+        }
+    }
+}
+
+/// Header of the complete frame at the front of `bytes`, and the size of its
+/// body.
+fn parse_header<T: Marker>(
+    bytes: &[u8],
+    config: ParseConfig,
+) -> Result<(Header<T>, usize), FrameError> {
+    let (frame, _) = Frame::<&[u8], T>::parse_prefix_with(bytes, config)?;
+    Ok((*frame.header(), frame.body().len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BufMut;
+    use zerocopy::AsBytes;
+
+    use crate::header::{Data, StreamId, Tag};
+
+    #[test]
+    fn split_from() {
+        let mut buf = BytesMut::new();
+        buf.put_slice(Header::data(StreamId::new(1), 300).as_bytes());
+        buf.put_slice(&[7; 100]);
+        assert_eq!(BytesFrame::split_from(&mut buf), Ok(None));
+        // Room is reserved for the rest of the body, and nothing is consumed.
+        assert!(buf.capacity() >= HEADER_LEN + 300);
+        assert_eq!(buf.len(), HEADER_LEN + 100);
+
+        buf.put_slice(&[7; 200]);
+        buf.put_slice(Header::ping(5).as_bytes());
+        let body_ptr = buf[HEADER_LEN..].as_ptr();
+        let frame = BytesFrame::split_from(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.tag(), Tag::Data);
+        assert_eq!(frame.body().len(), 300);
+        assert_eq!(frame.body().as_ptr(), body_ptr);
+        assert_eq!(buf.len(), HEADER_LEN);
+
+        let frame = BytesFrame::split_from(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.tag(), Tag::Ping);
+        assert!(buf.is_empty());
+        assert_eq!(BytesFrame::split_from(&mut buf), Ok(None));
+    }
+
+    #[test]
+    fn split_from_bytes() {
+        let mut buf = BytesMut::new();
+        buf.put_slice(Header::data(StreamId::new(1), 5).as_bytes());
+        buf.put_slice(b"hello");
+        let mut bytes = buf.freeze();
+        let mut partial = bytes.slice(..HEADER_LEN + 2);
+        assert_eq!(BytesFrame::split_from_bytes(&mut partial), Ok(None));
+        assert_eq!(partial.len(), HEADER_LEN + 2);
+
+        let frame = OwnedFrame::<Data, Bytes>::split_from_bytes(&mut bytes)
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.body(), &b"hello"[..]);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn split_from_error() {
+        let mut buf = BytesMut::new();
+        buf.put_slice(Header::ping(5).as_bytes());
+        assert_eq!(
+            OwnedFrame::<Data, Bytes>::split_from(&mut buf),
+            Err(FrameError::TagMismatch {
+                expected: Tag::Data,
+                actual: Tag::Ping
+            })
+        );
+        assert_eq!(buf.len(), HEADER_LEN);
+    }
+}
@@ -0,0 +1,399 @@
+//! Lifecycle of a stream, driven by the flags of the Data and WindowUpdate
+//! frames exchanged on it, and allocation of stream IDs.
+
+use core::error::Error;
+use core::fmt::{self, Display};
+
+use crate::flags::Flags;
+use crate::header::{StreamId, Tag};
+
+/// Side of the connection, which decides the parity of the stream IDs it
+/// allocates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// The side that initiated the connection, opening odd streams.
+    Client,
+    /// The side that accepted the connection, opening even streams.
+    Server,
+}
+
+impl Role {
+    /// Role of the other side of the connection.
+    pub fn peer(self) -> Role {
+        match self {
+            Role::Client => Role::Server,
+            Role::Server => Role::Client,
+        }
+    }
+
+    /// First stream ID allocated by this side.
+    fn first_id(self) -> u32 {
+        match self {
+            Role::Client => 1,
+            Role::Server => 2,
+        }
+    }
+
+    /// Whether `id` has the parity of the streams this side opens.
+    fn owns(self, id: u32) -> bool {
+        id % 2 == self.first_id() % 2
+    }
+}
+
+/// State of a stream, as seen from one side of the connection.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StreamState {
+    /// Opened locally, nothing sent yet.
+    #[default]
+    Init,
+    /// SYN sent, waiting for the peer's ACK.
+    SynSent,
+    /// SYN received from the peer, ACK not sent yet.
+    SynReceived,
+    /// Both sides may send data.
+    Established,
+    /// FIN sent, the peer may still send data.
+    LocalClose,
+    /// FIN received, we may still send data.
+    RemoteClose,
+    /// FIN sent and received.
+    Closed,
+    /// RST sent or received.
+    Reset,
+}
+
+impl StreamState {
+    /// Apply the flags of a `tag` frame received from the peer.
+    ///
+    /// Frames arriving after the stream was reset are ignored, since they may
+    /// have been sent before the peer learned about it.
+    pub fn on_recv(&mut self, tag: Tag, flags: Flags) -> Result<(), InvalidTransition> {
+        let invalid = InvalidTransition {
+            state: *self,
+            tag,
+            flags,
+        };
+        if *self == StreamState::Reset {
+            return Ok(());
+        }
+        if flags.contains(Flags::RST) {
+            *self = StreamState::Reset;
+            return Ok(());
+        }
+        if flags.contains(Flags::SYN) {
+            if *self != StreamState::Init {
+                return Err(invalid);
+            }
+            *self = StreamState::SynReceived;
+        }
+        match *self {
+            StreamState::Init => return Err(invalid),
+            StreamState::SynSent if flags.contains(Flags::ACK) => *self = StreamState::Established,
+            StreamState::RemoteClose | StreamState::Closed if tag == Tag::Data => {
+                return Err(invalid)
+            }
+            _ => {}
+        }
+        if flags.contains(Flags::FIN) {
+            *self = match *self {
+                StreamState::SynSent | StreamState::SynReceived | StreamState::Established => {
+                    StreamState::RemoteClose
+                }
+                StreamState::LocalClose => StreamState::Closed,
+                _ => return Err(invalid),
+            };
+        }
+        Ok(())
+    }
+
+    /// Flags the next frame sent on the stream must carry, or `None` if the
+    /// stream is closed for writing.
+    pub fn send_flags(&mut self) -> Option<Flags> {
+        match *self {
+            StreamState::Init => {
+                *self = StreamState::SynSent;
+                Some(Flags::SYN)
+            }
+            StreamState::SynReceived => {
+                *self = StreamState::Established;
+                Some(Flags::ACK)
+            }
+            StreamState::SynSent | StreamState::Established | StreamState::RemoteClose => {
+                Some(Flags::empty())
+            }
+            StreamState::LocalClose | StreamState::Closed | StreamState::Reset => None,
+        }
+    }
+
+    /// Flags of the frame closing our side of the stream, or `None` if it is
+    /// already closed for writing.
+    pub fn send_fin(&mut self) -> Option<Flags> {
+        let flags = self.send_flags()?;
+        *self = match *self {
+            StreamState::RemoteClose => StreamState::Closed,
+            _ => StreamState::LocalClose,
+        };
+        Some(flags | Flags::FIN)
+    }
+
+    /// Flags of the frame resetting the stream.
+    pub fn send_rst(&mut self) -> Flags {
+        *self = StreamState::Reset;
+        Flags::RST
+    }
+
+    /// Whether data can still be sent on the stream.
+    pub fn is_writable(self) -> bool {
+        !matches!(
+            self,
+            StreamState::LocalClose | StreamState::Closed | StreamState::Reset
+        )
+    }
+
+    /// Whether the peer may still send data on the stream.
+    pub fn is_readable(self) -> bool {
+        !matches!(
+            self,
+            StreamState::RemoteClose | StreamState::Closed | StreamState::Reset
+        )
+    }
+
+    /// Whether the stream is done in both directions.
+    pub fn is_closed(self) -> bool {
+        matches!(self, StreamState::Closed | StreamState::Reset)
+    }
+}
+
+/// Frame the peer is not allowed to send in the current state of its stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InvalidTransition {
+    /// State of the stream when the frame arrived.
+    pub state: StreamState,
+    /// Tag of the frame.
+    pub tag: Tag,
+    /// Flags set on the frame.
+    pub flags: Flags,
+}
+
+impl Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} frame with flags {} not allowed in state {:?}",
+            self.tag, self.flags, self.state
+        )
+    }
+}
+
+impl Error for InvalidTransition {}
+
+/// Allocator of the IDs of streams opened locally, checking the IDs of
+/// streams opened by the peer.
+///
+/// Clients open odd streams and servers even ones, each side using strictly
+/// increasing IDs. ID 0 is reserved for session frames.
+#[derive(Clone, Debug)]
+pub struct StreamIdAllocator {
+    role: Role,
+    // Next ID to allocate, or `None` once IDs are exhausted.
+    next_local: Option<u32>,
+    // Highest ID opened by the peer so far, 0 if none.
+    last_remote: u32,
+}
+
+impl StreamIdAllocator {
+    /// Allocator for the side of the connection playing `role`.
+    pub fn new(role: Role) -> Self {
+        StreamIdAllocator {
+            role,
+            next_local: Some(role.first_id()),
+            last_remote: 0,
+        }
+    }
+
+    /// Side of the connection the allocator works for.
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// ID of the next stream opened locally.
+    ///
+    /// Once IDs are exhausted the session should send a GoAway, since no new
+    /// stream can be opened on it.
+    pub fn allocate(&mut self) -> Result<StreamId, StreamIdError> {
+        let id = self.next_local.ok_or(StreamIdError::Exhausted)?;
+        self.next_local = id.checked_add(2);
+        Ok(StreamId::new(id))
+    }
+
+    /// Whether no stream can be opened locally anymore.
+    pub fn is_exhausted(&self) -> bool {
+        self.next_local.is_none()
+    }
+
+    /// Check the ID of a stream the peer opens with a SYN.
+    pub fn accept(&mut self, id: StreamId) -> Result<(), StreamIdError> {
+        let id = id.val();
+        if id == 0 {
+            return Err(StreamIdError::Reserved);
+        }
+        if !self.role.peer().owns(id) {
+            return Err(StreamIdError::WrongParity(id));
+        }
+        if id <= self.last_remote {
+            return Err(StreamIdError::NotIncreasing {
+                id,
+                last: self.last_remote,
+            });
+        }
+        self.last_remote = id;
+        Ok(())
+    }
+}
+
+/// Error raised by a [`StreamIdAllocator`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StreamIdError {
+    /// All the IDs of our parity were allocated.
+    Exhausted,
+    /// The peer opened a stream with ID 0, reserved for session frames.
+    Reserved,
+    /// The peer opened a stream with an ID of our parity.
+    WrongParity(u32),
+    /// The peer opened a stream with an ID not above the previous one.
+    NotIncreasing {
+        /// ID of the new stream.
+        id: u32,
+        /// Highest ID opened by the peer before it.
+        last: u32,
+    },
+}
+
+impl Display for StreamIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamIdError::Exhausted => f.write_str("stream IDs exhausted"),
+            StreamIdError::Reserved => f.write_str("stream ID 0 is reserved for the session"),
+            StreamIdError::WrongParity(id) => write!(f, "stream ID {id} has the wrong parity"),
+            StreamIdError::NotIncreasing { id, last } => {
+                write!(f, "stream ID {id} is not above the previous one, {last}")
+            }
+        }
+    }
+}
+
+impl Error for StreamIdError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn established() -> StreamState {
+        let mut state = StreamState::default();
+        assert_eq!(state.send_flags(), Some(Flags::SYN));
+        state.on_recv(Tag::WindowUpdate, Flags::ACK).unwrap();
+        assert_eq!(state, StreamState::Established);
+        state
+    }
+
+    #[test]
+    fn close() {
+        let mut state = established();
+        assert_eq!(state.send_fin(), Some(Flags::FIN));
+        assert_eq!(state, StreamState::LocalClose);
+        assert!(!state.is_writable());
+        assert_eq!(state.send_flags(), None);
+        state.on_recv(Tag::Data, Flags::FIN).unwrap();
+        assert!(state.is_closed());
+    }
+
+    #[test]
+    fn data_after_fin() {
+        let mut state = established();
+        state.on_recv(Tag::Data, Flags::FIN).unwrap();
+        assert_eq!(state, StreamState::RemoteClose);
+        assert_eq!(
+            state.on_recv(Tag::Data, Flags::empty()),
+            Err(InvalidTransition {
+                state: StreamState::RemoteClose,
+                tag: Tag::Data,
+                flags: Flags::empty(),
+            })
+        );
+        // Credit may still be granted for the data we send.
+        state.on_recv(Tag::WindowUpdate, Flags::empty()).unwrap();
+    }
+
+    #[test]
+    fn syn_on_open_stream() {
+        let mut state = established();
+        assert_eq!(
+            state.on_recv(Tag::WindowUpdate, Flags::SYN),
+            Err(InvalidTransition {
+                state: StreamState::Established,
+                tag: Tag::WindowUpdate,
+                flags: Flags::SYN,
+            })
+        );
+    }
+
+    impl StreamIdAllocator {
+        /// Allocator about to hand out `next_local`.
+        pub(crate) fn starting_at(role: Role, next_local: u32) -> Self {
+            StreamIdAllocator {
+                role,
+                next_local: Some(next_local),
+                last_remote: 0,
+            }
+        }
+    }
+
+    #[test]
+    fn allocate() {
+        let mut client = StreamIdAllocator::new(Role::Client);
+        let mut server = StreamIdAllocator::new(Role::Server);
+        assert_eq!(client.allocate().unwrap().val(), 1);
+        assert_eq!(client.allocate().unwrap().val(), 3);
+        assert_eq!(server.allocate().unwrap().val(), 2);
+        assert_eq!(server.allocate().unwrap().val(), 4);
+    }
+
+    #[test]
+    fn exhausted() {
+        let mut client = StreamIdAllocator::starting_at(Role::Client, u32::MAX - 2);
+        assert_eq!(client.allocate().unwrap().val(), u32::MAX - 2);
+        assert_eq!(client.allocate().unwrap().val(), u32::MAX);
+        assert!(client.is_exhausted());
+        assert_eq!(client.allocate(), Err(StreamIdError::Exhausted));
+
+        let mut server = StreamIdAllocator::starting_at(Role::Server, u32::MAX - 1);
+        assert_eq!(server.allocate().unwrap().val(), u32::MAX - 1);
+        assert_eq!(server.allocate(), Err(StreamIdError::Exhausted));
+    }
+
+    #[test]
+    fn accept() {
+        let mut server = StreamIdAllocator::new(Role::Server);
+        let mut accept = |id| server.accept(StreamId::new(id));
+        assert_eq!(accept(0), Err(StreamIdError::Reserved));
+        assert_eq!(accept(2), Err(StreamIdError::WrongParity(2)));
+        assert_eq!(accept(3), Ok(()));
+        assert_eq!(
+            accept(3),
+            Err(StreamIdError::NotIncreasing { id: 3, last: 3 })
+        );
+        assert_eq!(
+            accept(1),
+            Err(StreamIdError::NotIncreasing { id: 1, last: 3 })
+        );
+        assert_eq!(accept(7), Ok(()));
+    }
+
+    #[test]
+    fn frames_after_reset() {
+        let mut state = established();
+        assert_eq!(state.send_rst(), Flags::RST);
+        state.on_recv(Tag::Data, Flags::SYN | Flags::FIN).unwrap();
+        assert_eq!(state, StreamState::Reset);
+    }
+}
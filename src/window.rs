@@ -0,0 +1,247 @@
+//! Per-stream flow control: credit for sending data, granted by the peer
+//! with WindowUpdate frames, and credit granted to the peer in return.
+
+use core::error::Error;
+use core::fmt::{self, Display};
+
+/// Size of the send and receive windows of a stream when it is opened.
+pub const INITIAL_WINDOW: u32 = 256 * 1024;
+
+/// Credit for sending data on a stream.
+#[derive(Copy, Clone, Debug)]
+pub struct SendWindow {
+    credit: u32,
+}
+
+impl Default for SendWindow {
+    fn default() -> Self {
+        SendWindow {
+            credit: INITIAL_WINDOW,
+        }
+    }
+}
+
+impl SendWindow {
+    /// Number of bytes that can be sent before the peer grants more.
+    pub fn credit(&self) -> u32 {
+        self.credit
+    }
+
+    /// Take credit for sending up to `len` bytes, returning the number of
+    /// bytes that can actually be sent.
+    pub fn consume(&mut self, len: usize) -> usize {
+        let len = len.min(self.credit as usize);
+        self.credit -= len as u32;
+        len
+    }
+
+    /// Grow the window by the `delta` of a WindowUpdate from the peer.
+    pub fn grow(&mut self, delta: u32) -> Result<(), FlowControlError> {
+        self.credit = self
+            .credit
+            .checked_add(delta)
+            .ok_or(FlowControlError::WindowOverflow {
+                credit: self.credit,
+                delta,
+            })?;
+        Ok(())
+    }
+}
+
+/// Credit granted to the peer for sending data on a stream.
+///
+/// Of the `max` bytes of the window, `credit` may still be sent by the peer,
+/// `pending` were consumed by the application but not granted back yet, and
+/// the rest were received but not consumed yet.
+#[derive(Copy, Clone, Debug)]
+pub struct RecvWindow {
+    max: u32,
+    credit: u32,
+    pending: u32,
+}
+
+impl RecvWindow {
+    /// Window of `max` bytes, at least [`INITIAL_WINDOW`].
+    ///
+    /// The peer starts with [`INITIAL_WINDOW`] bytes of credit, the rest being
+    /// pending until granted by [`RecvWindow::grant`].
+    pub fn new(max: u32) -> Self {
+        let max = max.max(INITIAL_WINDOW);
+        RecvWindow {
+            max,
+            credit: INITIAL_WINDOW,
+            pending: max - INITIAL_WINDOW,
+        }
+    }
+
+    /// Size of the window.
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// Number of bytes the peer may still send.
+    pub fn credit(&self) -> u32 {
+        self.credit
+    }
+
+    /// Number of bytes received but not consumed by the application.
+    pub fn buffered(&self) -> u32 {
+        self.max - self.credit - self.pending
+    }
+
+    /// Account for a Data frame of `len` bytes from the peer.
+    pub fn on_data(&mut self, len: u32) -> Result<(), FlowControlError> {
+        if len > self.credit {
+            return Err(FlowControlError::WindowExceeded {
+                len,
+                credit: self.credit,
+            });
+        }
+        self.credit -= len;
+        Ok(())
+    }
+
+    /// Account for `len` bytes consumed by the application, returning the
+    /// delta of the WindowUpdate to send once `threshold` bytes are pending.
+    ///
+    /// The threshold is capped to the size of the window, since no more bytes
+    /// than that can ever be pending.
+    pub fn consume(&mut self, len: u32, threshold: u32) -> Option<u32> {
+        self.pending += len.min(self.buffered());
+        if self.pending < threshold.clamp(1, self.max) {
+            return None;
+        }
+        Some(self.grant())
+    }
+
+    /// Grow the window to `max` bytes, the extra credit being pending.
+    pub fn grow_to(&mut self, max: u32) {
+        if max > self.max {
+            self.pending += max - self.max;
+            self.max = max;
+        }
+    }
+
+    /// Grant the pending bytes to the peer, returning the delta of the
+    /// WindowUpdate to send.
+    pub fn grant(&mut self) -> u32 {
+        let delta = self.pending;
+        self.credit += delta;
+        self.pending = 0;
+        delta
+    }
+}
+
+/// Violation of flow control by the peer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlowControlError {
+    /// The peer sent more data than it was granted.
+    WindowExceeded {
+        /// Size of the Data frame body.
+        len: u32,
+        /// Credit the peer had left.
+        credit: u32,
+    },
+    /// A WindowUpdate grows the send window past `u32::MAX`.
+    WindowOverflow {
+        /// Credit before the update.
+        credit: u32,
+        /// Delta of the update.
+        delta: u32,
+    },
+}
+
+impl Display for FlowControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlowControlError::WindowExceeded { len, credit } => {
+                write!(f, "{len} bytes of data exceed the {credit} bytes granted")
+            }
+            FlowControlError::WindowOverflow { credit, delta } => {
+                write!(
+                    f,
+                    "window update of {delta} overflows the {credit} bytes window"
+                )
+            }
+        }
+    }
+}
+
+impl Error for FlowControlError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that the credit and pending bytes of `window` fit in it.
+    fn check(window: &RecvWindow) {
+        assert!(window.credit + window.pending <= window.max);
+    }
+
+    #[test]
+    fn send_window() {
+        let mut window = SendWindow::default();
+        assert_eq!(
+            window.consume(INITIAL_WINDOW as usize + 1),
+            INITIAL_WINDOW as usize
+        );
+        assert_eq!(window.consume(1), 0);
+        window.grow(u32::MAX).unwrap();
+        assert_eq!(
+            window.grow(1),
+            Err(FlowControlError::WindowOverflow {
+                credit: u32::MAX,
+                delta: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn window_exceeded() {
+        let mut window = RecvWindow::new(INITIAL_WINDOW);
+        window.on_data(INITIAL_WINDOW - 1).unwrap();
+        assert_eq!(
+            window.on_data(2),
+            Err(FlowControlError::WindowExceeded { len: 2, credit: 1 })
+        );
+    }
+
+    #[test]
+    fn consume() {
+        let mut window = RecvWindow::new(INITIAL_WINDOW);
+        window.on_data(1000).unwrap();
+        assert_eq!(window.consume(600, 1000), None);
+        assert_eq!(window.buffered(), 400);
+        // More bytes than received are only counted once received.
+        assert_eq!(window.consume(1000, 1000), Some(1000));
+        assert_eq!(window.credit(), INITIAL_WINDOW);
+        check(&window);
+    }
+
+    #[test]
+    fn grow_to_and_grant() {
+        let mut window = RecvWindow::new(INITIAL_WINDOW);
+        window.on_data(INITIAL_WINDOW).unwrap();
+        window.grow_to(2 * INITIAL_WINDOW);
+        check(&window);
+        // Windows never shrink.
+        window.grow_to(INITIAL_WINDOW);
+        assert_eq!(window.max(), 2 * INITIAL_WINDOW);
+        assert_eq!(window.grant(), INITIAL_WINDOW);
+        assert_eq!(window.grant(), 0);
+        check(&window);
+        assert_eq!(window.consume(INITIAL_WINDOW, 1), Some(INITIAL_WINDOW));
+        assert_eq!(window.credit(), window.max());
+        check(&window);
+    }
+
+    #[test]
+    fn larger_window() {
+        let mut window = RecvWindow::new(4 * INITIAL_WINDOW);
+        assert_eq!(window.credit(), INITIAL_WINDOW);
+        check(&window);
+        assert_eq!(window.grant(), 3 * INITIAL_WINDOW);
+        assert_eq!(window.credit(), window.max());
+        check(&window);
+    }
+}